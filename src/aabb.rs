@@ -0,0 +1,89 @@
+use crate::{ray::Ray, vec3::Point};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Aabb {
+    minimum: Point,
+    maximum: Point,
+}
+
+impl Aabb {
+    pub fn new(minimum: Point, maximum: Point) -> Self {
+        Self { minimum, maximum }
+    }
+    pub fn min(&self) -> Point {
+        self.minimum
+    }
+    pub fn max(&self) -> Point {
+        self.maximum
+    }
+    pub fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.hit_interval(r, t_min, t_max).is_some()
+    }
+    // Grows flat boxes so that rays can still hit them, e.g. for axis-aligned quads.
+    pub fn padded(&self, delta: f64) -> Self {
+        let mut minimum = self.minimum;
+        let mut maximum = self.maximum;
+        for a in 0..3 {
+            if maximum[a] - minimum[a] < delta {
+                minimum[a] -= delta / 2.0;
+                maximum[a] += delta / 2.0;
+            }
+        }
+        Self::new(minimum, maximum)
+    }
+    pub fn corners(&self) -> [Point; 8] {
+        let mut corners = [Point::default(); 8];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            for a in 0..3 {
+                corner[a] = if i & (1 << a) == 0 {
+                    self.minimum[a]
+                } else {
+                    self.maximum[a]
+                };
+            }
+        }
+        corners
+    }
+    pub fn from_points(points: &[Point]) -> Self {
+        let mut minimum = Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        let mut maximum = -minimum;
+        for p in points.iter() {
+            for a in 0..3 {
+                minimum[a] = minimum[a].min(p[a]);
+                maximum[a] = maximum[a].max(p[a]);
+            }
+        }
+        Self::new(minimum, maximum)
+    }
+    // Returns the parametric interval of the ray inside the box, clipped to [t_min, t_max].
+    pub fn hit_interval(&self, r: &Ray, mut t_min: f64, mut t_max: f64) -> Option<(f64, f64)> {
+        for a in 0..3 {
+            let inv_d = 1.0 / r.direction[a];
+            let mut t0 = (self.minimum[a] - r.origin[a]) * inv_d;
+            let mut t1 = (self.maximum[a] - r.origin[a]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max <= t_min {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+}
+
+pub fn surrounding_box(box0: &Aabb, box1: &Aabb) -> Aabb {
+    let small = Point::new(
+        box0.min()[0].min(box1.min()[0]),
+        box0.min()[1].min(box1.min()[1]),
+        box0.min()[2].min(box1.min()[2]),
+    );
+    let big = Point::new(
+        box0.max()[0].max(box1.max()[0]),
+        box0.max()[1].max(box1.max()[1]),
+        box0.max()[2].max(box1.max()[2]),
+    );
+    Aabb::new(small, big)
+}
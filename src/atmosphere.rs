@@ -0,0 +1,249 @@
+use crate::{
+    hittable::{HitRecord, Hittable},
+    material::HenyeyGreenstein,
+    ray::Ray,
+    vec3::{Color, Direction},
+};
+use rand::{Rng, RngCore};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sun {
+    direction: Direction,
+    irradiance: Color,
+    cos_angular_radius: f64,
+}
+
+impl Sun {
+    // `direction` points towards the sun; `angular_radius` is in degrees.
+    pub fn new(direction: Direction, irradiance: Color, angular_radius: f64) -> Self {
+        Self {
+            direction: direction.unit(),
+            irradiance,
+            cos_angular_radius: angular_radius.to_radians().cos(),
+        }
+    }
+    fn radiance(&self) -> Color {
+        let solid_angle = 2.0 * std::f64::consts::PI * (1.0 - self.cos_angular_radius);
+        self.irradiance / solid_angle
+    }
+}
+
+// Scene-wide fog whose density decays exponentially with height:
+// density * exp(-falloff * (y - base_height)).
+#[derive(Debug, Clone, Copy)]
+pub struct HeightFog {
+    density: f64,
+    base_height: f64,
+    falloff: f64,
+    anisotropy: f64,
+    albedo: Color,
+    sun: Option<Sun>,
+}
+
+impl HeightFog {
+    pub fn new(density: f64, base_height: f64, falloff: f64, anisotropy: f64) -> Self {
+        Self {
+            density,
+            base_height,
+            falloff,
+            anisotropy: anisotropy.clamp(-0.99, 0.99),
+            albedo: Color::new(1.0, 1.0, 1.0),
+            sun: None,
+        }
+    }
+    pub fn with_albedo(mut self, albedo: Color) -> Self {
+        self.albedo = albedo;
+        self
+    }
+    pub fn with_sun(mut self, sun: Sun) -> Self {
+        self.sun = Some(sun);
+        self
+    }
+    // Optical depth along the unit ray (origin, direction) from 0 to `distance`.
+    fn optical_depth(&self, r: &Ray, distance: f64) -> f64 {
+        let a = self.density * (-self.falloff * (r.origin[1] - self.base_height)).exp();
+        let b = self.falloff * r.direction[1];
+        if b.abs() < 1e-9 {
+            a * distance
+        } else if distance.is_infinite() {
+            if b > 0.0 {
+                a / b
+            } else {
+                f64::INFINITY
+            }
+        } else {
+            a * (1.0 - (-b * distance).exp()) / b
+        }
+    }
+    // Samples a free-flight distance along the unit ray, or None if it escapes to infinity.
+    fn sample_distance(&self, r: &Ray, rng: &mut dyn RngCore) -> Option<f64> {
+        let tau = -(1.0 - rng.gen::<f64>()).ln();
+        let a = self.density * (-self.falloff * (r.origin[1] - self.base_height)).exp();
+        let b = self.falloff * r.direction[1];
+        if a <= 0.0 {
+            return None;
+        }
+        if b.abs() < 1e-9 {
+            return Some(tau / a);
+        }
+        let x = 1.0 - tau * b / a;
+        if x <= 0.0 {
+            None
+        } else {
+            Some(-x.ln() / b)
+        }
+    }
+    fn background(&self, r: &Ray, include_sun: bool) -> Color {
+        let mut color = r.background();
+        if let Some(sun) = &self.sun {
+            if include_sun && r.direction.dot(&sun.direction) > sun.cos_angular_radius {
+                color += sun.radiance();
+            }
+        }
+        color
+    }
+    // Radiance along `r` through the fog. Sunlight scattered by the fog is gathered with
+    // shadow rays, which is what produces light shafts behind occluders.
+    pub fn color<T>(&self, r: &Ray, world: &T, rng: &mut dyn RngCore, depth: i64) -> Color
+    where
+        T: Hittable,
+    {
+        let unit = Ray {
+            origin: r.origin,
+            direction: r.direction.unit(),
+            wavelength: r.wavelength,
+            time: r.time,
+        };
+        self.trace(&unit, world, rng, depth, true)
+    }
+    fn trace<T>(
+        &self,
+        r: &Ray,
+        world: &T,
+        rng: &mut dyn RngCore,
+        depth: i64,
+        include_sun: bool,
+    ) -> Color
+    where
+        T: Hittable,
+    {
+        if depth <= 0 {
+            return Color::default();
+        }
+        let mut rec = HitRecord::default();
+        let surface_distance = if world.hit(r, 0.001, f64::INFINITY, &mut rec) {
+            rec.t
+        } else {
+            f64::INFINITY
+        };
+
+        match self.sample_distance(r, rng) {
+            Some(distance) if distance < surface_distance => {
+                let point = r.at(distance);
+                let mut color = Color::default();
+                if let Some(sun) = &self.sun {
+                    let shadow = Ray {
+                        origin: point,
+                        direction: sun.direction,
+                        wavelength: r.wavelength,
+                        time: r.time,
+                    };
+                    let mut shadow_rec = HitRecord::default();
+                    if !world.hit(&shadow, 0.001, f64::INFINITY, &mut shadow_rec) {
+                        let transmittance = (-self.optical_depth(&shadow, f64::INFINITY)).exp();
+                        let phase = HenyeyGreenstein::phase(
+                            r.direction.dot(&sun.direction),
+                            self.anisotropy,
+                        );
+                        color += transmittance * phase * sun.irradiance;
+                    }
+                }
+                let scattered = Ray {
+                    origin: point,
+                    direction: HenyeyGreenstein::sample(&r.direction, self.anisotropy, rng),
+                    wavelength: r.wavelength,
+                    time: r.time,
+                };
+                color += self.trace(&scattered, world, rng, depth - 1, false);
+                self.albedo * color
+            }
+            _ if surface_distance.is_finite() => {
+                let mut scattered = Ray::default();
+                let mut attenuation = Color::default();
+                let emitted = rec.material.emitted(rec.u, rec.v, &rec.point);
+                if rec
+                    .material
+                    .scatter(r, &rec, &mut attenuation, &mut scattered, rng)
+                {
+                    scattered.direction = scattered.direction.unit();
+                    emitted + attenuation * self.trace(&scattered, world, rng, depth - 1, true)
+                } else {
+                    emitted
+                }
+            }
+            _ => self.background(r, include_sun),
+        }
+    }
+}
+
+#[test]
+fn test_height_fog_optical_depth() {
+    use crate::vec3::{Point, Vec3};
+
+    let fog = HeightFog::new(0.3, 1.0, 0.7, 0.0);
+    let density = |p: &Point| 0.3 * (-0.7 * (p[1] - 1.0)).exp();
+    let ray = |direction: Vec3| Ray {
+        origin: Point::new(0.0, 0.5, 0.0),
+        direction: direction.unit(),
+        ..Ray::default()
+    };
+    // Midpoint rule against the closed form, for rays climbing, descending and level.
+    for direction in [
+        Vec3::new(0.6, 0.8, 0.0),
+        Vec3::new(0.6, -0.8, 0.0),
+        Vec3::new(1.0, 0.0, 0.0),
+    ]
+    .iter()
+    {
+        let r = ray(*direction);
+        let steps = 10000;
+        let dt = 5.0 / steps as f64;
+        let numeric: f64 = (0..steps)
+            .map(|i| density(&r.at((i as f64 + 0.5) * dt)) * dt)
+            .sum();
+        assert!((fog.optical_depth(&r, 5.0) - numeric).abs() < 1e-6 * numeric);
+    }
+    // Climbing rays see finite optical depth all the way up, descending ones do not.
+    let up = ray(Vec3::new(0.6, 0.8, 0.0));
+    let a = density(&up.origin);
+    assert!((fog.optical_depth(&up, f64::INFINITY) - a / (0.7 * 0.8)).abs() < 1e-12);
+    assert!(fog
+        .optical_depth(&ray(Vec3::new(0.6, -0.8, 0.0)), f64::INFINITY)
+        .is_infinite());
+}
+
+#[test]
+fn test_height_fog_sample_distance() {
+    use crate::vec3::{Point, Vec3};
+
+    // Free-flight distances follow P(d <= x) = 1 - exp(-optical_depth(x)), and climbing rays
+    // escape with probability exp(-optical_depth(inf)).
+    let fog = HeightFog::new(0.3, 1.0, 0.7, 0.0);
+    let mut rng = rand::thread_rng();
+    let n = 20000;
+    for direction in [Vec3::new(0.6, 0.8, 0.0), Vec3::new(1.0, 0.0, 0.0)].iter() {
+        let r = Ray {
+            origin: Point::new(0.0, 0.5, 0.0),
+            direction: direction.unit(),
+            ..Ray::default()
+        };
+        let samples: Vec<Option<f64>> = (0..n).map(|_| fog.sample_distance(&r, &mut rng)).collect();
+        let fraction = |f: &dyn Fn(&Option<f64>) -> bool| {
+            samples.iter().filter(|s| f(s)).count() as f64 / n as f64
+        };
+        let near = fraction(&|s| s.is_some_and(|d| d <= 2.0));
+        assert!((near - (1.0 - (-fog.optical_depth(&r, 2.0)).exp())).abs() < 0.02);
+        let escaped = fraction(&|s| s.is_none());
+        assert!((escaped - (-fog.optical_depth(&r, f64::INFINITY)).exp()).abs() < 0.02);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{
+    aabb::{surrounding_box, Aabb},
+    hittable::{HitRecord, Hittable},
+    ray::Ray,
+};
+
+const LEAF_SIZE: usize = 4;
+
+#[derive(Debug, Clone)]
+enum Node {
+    Leaf {
+        start: usize,
+        end: usize,
+    },
+    Interior {
+        left: usize,
+        right: usize,
+        axis: usize,
+    },
+}
+
+// Bounding volume hierarchy over a flat node array. Objects without a bounding box are kept
+// aside and tested on every ray.
+#[derive(Clone)]
+pub struct Bvh<T>
+where
+    T: Hittable,
+{
+    objects: Vec<T>,
+    unbounded: Vec<T>,
+    nodes: Vec<(Aabb, Node)>,
+}
+
+impl<T> Bvh<T>
+where
+    T: Hittable,
+{
+    pub fn new(objects: Vec<T>, time0: f64, time1: f64) -> Self {
+        let mut bounded = Vec::new();
+        let mut unbounded = Vec::new();
+        for object in objects {
+            let mut output_box = Aabb::default();
+            if object.bounding_box(time0, time1, &mut output_box) {
+                bounded.push((output_box, Some(object)));
+            } else {
+                unbounded.push(object);
+            }
+        }
+        let boxes: Vec<Aabb> = bounded.iter().map(|(b, _)| *b).collect();
+        let mut order: Vec<usize> = (0..bounded.len()).collect();
+        let mut nodes = Vec::new();
+        if !order.is_empty() {
+            build(&boxes, &mut order, 0, &mut nodes);
+        }
+        let objects = order
+            .into_iter()
+            .map(|i| bounded[i].1.take().unwrap())
+            .collect();
+        Self {
+            objects,
+            unbounded,
+            nodes,
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.objects.len() + self.unbounded.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Splits order[..] at the median centroid along the widest axis; returns the node index.
+fn build(
+    boxes: &[Aabb],
+    order: &mut [usize],
+    offset: usize,
+    nodes: &mut Vec<(Aabb, Node)>,
+) -> usize {
+    let bounds = order[1..]
+        .iter()
+        .fold(boxes[order[0]], |acc, &i| surrounding_box(&acc, &boxes[i]));
+    let index = nodes.len();
+    if order.len() <= LEAF_SIZE {
+        nodes.push((
+            bounds,
+            Node::Leaf {
+                start: offset,
+                end: offset + order.len(),
+            },
+        ));
+        return index;
+    }
+    let centroid = |i: usize, axis: usize| boxes[i].min()[axis] + boxes[i].max()[axis];
+    let centroids = Aabb::from_points(
+        &order
+            .iter()
+            .map(|&i| (boxes[i].min() + boxes[i].max()) * 0.5)
+            .collect::<Vec<_>>(),
+    );
+    let extent = centroids.max() - centroids.min();
+    let axis = if extent[0] > extent[1] && extent[0] > extent[2] {
+        0
+    } else if extent[1] > extent[2] {
+        1
+    } else {
+        2
+    };
+    let mid = order.len() / 2;
+    order.select_nth_unstable_by(mid, |&a, &b| {
+        // total_cmp, so primitives with NaN coordinates cannot abort the build.
+        centroid(a, axis).total_cmp(&centroid(b, axis))
+    });
+    // Placeholder, patched once both children exist.
+    nodes.push((bounds, Node::Leaf { start: 0, end: 0 }));
+    let (left_order, right_order) = order.split_at_mut(mid);
+    let left = build(boxes, left_order, offset, nodes);
+    let right = build(boxes, right_order, offset + mid, nodes);
+    nodes[index].1 = Node::Interior { left, right, axis };
+    index
+}
+
+impl<T> Hittable for Bvh<T>
+where
+    T: Hittable,
+{
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let mut temp = HitRecord::default();
+        let mut hit_anything = false;
+        let mut closest_so_far = t_max;
+        for object in self.unbounded.iter() {
+            temp.clear_attributes();
+            if object.hit(r, t_min, closest_so_far, &mut temp) {
+                hit_anything = true;
+                closest_so_far = temp.t;
+                *rec = temp.clone();
+            }
+        }
+        if self.nodes.is_empty() {
+            return hit_anything;
+        }
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let (bounds, node) = &self.nodes[index];
+            if !bounds.hit(r, t_min, closest_so_far) {
+                continue;
+            }
+            match *node {
+                Node::Leaf { start, end } => {
+                    for object in self.objects[start..end].iter() {
+                        temp.clear_attributes();
+                        if object.hit(r, t_min, closest_so_far, &mut temp) {
+                            hit_anything = true;
+                            closest_so_far = temp.t;
+                            *rec = temp.clone();
+                        }
+                    }
+                }
+                Node::Interior { left, right, axis } => {
+                    // Visit the nearer child first so the farther one is more likely culled.
+                    if r.direction[axis] < 0.0 {
+                        stack.push(left);
+                        stack.push(right);
+                    } else {
+                        stack.push(right);
+                        stack.push(left);
+                    }
+                }
+            }
+        }
+        hit_anything
+    }
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        if !self.unbounded.is_empty() || self.nodes.is_empty() {
+            return false;
+        }
+        *output_box = self.nodes[0].0;
+        true
+    }
+}
+
+#[test]
+fn test_bvh_nan_primitive() {
+    use crate::{material::Lambertian, sphere::Sphere, vec3::Color, vec3::Point, vec3::Vec3};
+    use std::rc::Rc;
+
+    let material = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let mut spheres: Vec<Sphere> = (0..8)
+        .map(|i| Sphere::new(Point::new(i as f64 * 3.0, 0.0, 0.0), 1.0, material.clone()))
+        .collect();
+    spheres.push(Sphere::new(Point::new(f64::NAN, 0.0, 0.0), 1.0, material));
+    let bvh = Bvh::new(spheres, 0.0, 0.0);
+    let r = Ray {
+        origin: Point::new(6.0, 0.0, 5.0),
+        direction: Vec3::new(0.0, 0.0, -1.0),
+        ..Ray::default()
+    };
+    let mut rec = HitRecord::default();
+    assert!(bvh.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 4.0).abs() < 1e-9);
+}
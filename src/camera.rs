@@ -2,8 +2,57 @@ use crate::{
     ray::{degrees_to_radians, Ray},
     vec3::{Point, Vec3},
 };
+use rand::Rng;
+use std::f64::consts::PI;
 
-pub struct Camera {
+// Maps image coordinates to camera rays. `s` runs from 0 at the left edge to 1 at the right,
+// `t` from 0 at the bottom to 1 at the top. None marks a pixel outside the projection, such
+// as the corners around a circular fisheye, which renders black.
+pub trait Camera {
+    fn ray(&self, s: f64, t: f64, rng: &mut dyn rand::RngCore) -> Option<Ray>;
+}
+
+// Orthonormal camera frame: `w` points backwards, away from `lookat`.
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    origin: Point,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+}
+
+impl Frame {
+    fn new(lookfrom: Point, lookat: Point, vup: Vec3) -> Self {
+        let w = (lookfrom - lookat).unit();
+        let u = vup.cross(&w).unit();
+        let v = w.cross(&u);
+        Self {
+            origin: lookfrom,
+            u,
+            v,
+            w,
+        }
+    }
+    // Turns a direction in camera space (x right, y up, z forward) into a ray.
+    fn ray(&self, origin: Point, local: Vec3, time: f64) -> Ray {
+        Ray {
+            origin,
+            direction: local[0] * self.u + local[1] * self.v - local[2] * self.w,
+            wavelength: None,
+            time,
+        }
+    }
+}
+
+fn shutter_time(time0: f64, time1: f64, rng: &mut dyn rand::RngCore) -> f64 {
+    if time1 > time0 {
+        rng.gen_range(time0..time1)
+    } else {
+        time0
+    }
+}
+
+pub struct PerspectiveCamera {
     origin: Point,
     lower_left_corner: Point,
     horizontal: Vec3,
@@ -12,9 +61,11 @@ pub struct Camera {
     v: Vec3,
     _w: Vec3,
     lens_radius: f64,
+    time0: f64, // shutter open time
+    time1: f64, // shutter close time
 }
 
-impl Camera {
+impl PerspectiveCamera {
     pub fn new(
         lookfrom: Point,
         lookat: Point,
@@ -29,11 +80,7 @@ impl Camera {
         let viewport_height = 2.0 * h;
         let viewport_width = aspect_ratio * viewport_height;
 
-        let w = (lookfrom - lookat).unit();
-        let u = vup.cross(&w).unit();
-        let v = w.cross(&u);
-
-        let origin = lookfrom;
+        let Frame { origin, u, v, w } = Frame::new(lookfrom, lookat, vup);
         let horizontal = focus_disk * viewport_width * u;
         let vertical = focus_disk * viewport_height * v;
         let lower_left_corner = origin - horizontal / 2.0 - vertical / 2.0 - focus_disk * w;
@@ -47,17 +94,561 @@ impl Camera {
             v,
             _w: w,
             lens_radius,
+            time0: 0.0,
+            time1: 0.0,
         }
     }
-    pub fn ray(&self, s: f64, t: f64, rng: &mut dyn rand::RngCore) -> Ray {
+    pub fn with_shutter(mut self, time0: f64, time1: f64) -> Self {
+        self.time0 = time0;
+        self.time1 = time1;
+        self
+    }
+}
+
+impl Camera for PerspectiveCamera {
+    fn ray(&self, s: f64, t: f64, rng: &mut dyn rand::RngCore) -> Option<Ray> {
         let rd = self.lens_radius * Vec3::new_random_in_unit_disk(rng);
         let offset = self.u * rd[0] + self.v * rd[1];
 
-        Ray {
+        Some(Ray {
             origin: self.origin + offset,
             direction: self.lower_left_corner + s * self.horizontal + t * self.vertical
                 - self.origin
                 - offset,
+            wavelength: None,
+            time: shutter_time(self.time0, self.time1, rng),
+        })
+    }
+}
+
+// Parallel rays along the view direction from a `height` by `height * aspect_ratio` window
+// centred on `lookfrom`.
+pub struct OrthographicCamera {
+    frame: Frame,
+    width: f64,
+    height: f64,
+    time0: f64,
+    time1: f64,
+}
+
+impl OrthographicCamera {
+    pub fn new(lookfrom: Point, lookat: Point, vup: Vec3, height: f64, aspect_ratio: f64) -> Self {
+        Self {
+            frame: Frame::new(lookfrom, lookat, vup),
+            width: height * aspect_ratio,
+            height,
+            time0: 0.0,
+            time1: 0.0,
+        }
+    }
+    pub fn with_shutter(mut self, time0: f64, time1: f64) -> Self {
+        self.time0 = time0;
+        self.time1 = time1;
+        self
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn ray(&self, s: f64, t: f64, rng: &mut dyn rand::RngCore) -> Option<Ray> {
+        let frame = &self.frame;
+        let origin =
+            frame.origin + (s - 0.5) * self.width * frame.u + (t - 0.5) * self.height * frame.v;
+        Some(frame.ray(
+            origin,
+            Vec3::new(0.0, 0.0, 1.0),
+            shutter_time(self.time0, self.time1, rng),
+        ))
+    }
+}
+
+// How a fisheye lens maps the angle from its axis to the distance from the image centre.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FisheyeMapping {
+    // Distance proportional to the angle.
+    Equidistant,
+    // Distance proportional to sin(angle / 2), preserving solid angle.
+    Equisolid,
+}
+
+// A circular fisheye whose image circle spans the shorter side of the image and covers `fov`
+// degrees, up to 360.
+pub struct FisheyeCamera {
+    frame: Frame,
+    mapping: FisheyeMapping,
+    half_fov: f64,
+    aspect_ratio: f64,
+    time0: f64,
+    time1: f64,
+}
+
+impl FisheyeCamera {
+    pub fn new(
+        lookfrom: Point,
+        lookat: Point,
+        vup: Vec3,
+        mapping: FisheyeMapping,
+        fov: f64,
+        aspect_ratio: f64,
+    ) -> Self {
+        Self {
+            frame: Frame::new(lookfrom, lookat, vup),
+            mapping,
+            half_fov: degrees_to_radians(fov.clamp(0.0, 360.0)) / 2.0,
+            aspect_ratio,
+            time0: 0.0,
+            time1: 0.0,
+        }
+    }
+    pub fn with_shutter(mut self, time0: f64, time1: f64) -> Self {
+        self.time0 = time0;
+        self.time1 = time1;
+        self
+    }
+}
+
+impl Camera for FisheyeCamera {
+    fn ray(&self, s: f64, t: f64, rng: &mut dyn rand::RngCore) -> Option<Ray> {
+        // Image coordinates scaled so the circle has radius 1.
+        let (x, y) = if self.aspect_ratio >= 1.0 {
+            (2.0 * (s - 0.5) * self.aspect_ratio, 2.0 * (t - 0.5))
+        } else {
+            (2.0 * (s - 0.5), 2.0 * (t - 0.5) / self.aspect_ratio)
+        };
+        let r = (x * x + y * y).sqrt();
+        if r > 1.0 {
+            return None;
+        }
+        let theta = match self.mapping {
+            FisheyeMapping::Equidistant => r * self.half_fov,
+            FisheyeMapping::Equisolid => 2.0 * (r * (self.half_fov / 2.0).sin()).asin(),
+        };
+        let phi = y.atan2(x);
+        let local = Vec3::new(
+            theta.sin() * phi.cos(),
+            theta.sin() * phi.sin(),
+            theta.cos(),
+        );
+        Some(self.frame.ray(
+            self.frame.origin,
+            local,
+            shutter_time(self.time0, self.time1, rng),
+        ))
+    }
+}
+
+// A full 360 by 180 degree panorama with `lookat` in the middle of the image; meant for
+// images twice as wide as they are tall.
+pub struct EquirectangularCamera {
+    frame: Frame,
+    eye_offset: f64,
+    time0: f64,
+    time1: f64,
+}
+
+impl EquirectangularCamera {
+    pub fn new(lookfrom: Point, lookat: Point, vup: Vec3) -> Self {
+        Self {
+            frame: Frame::new(lookfrom, lookat, vup),
+            eye_offset: 0.0,
+            time0: 0.0,
+            time1: 0.0,
+        }
+    }
+    pub fn with_shutter(mut self, time0: f64, time1: f64) -> Self {
+        self.time0 = time0;
+        self.time1 = time1;
+        self
+    }
+    // Omni-directional stereo: every ray starts `offset` to the right of `lookfrom` as seen
+    // when facing its longitude, so the eye circles the viewpoint as the view turns. Negative
+    // offsets give the left eye.
+    pub fn with_eye_offset(mut self, offset: f64) -> Self {
+        self.eye_offset = offset;
+        self
+    }
+}
+
+impl Camera for EquirectangularCamera {
+    fn ray(&self, s: f64, t: f64, rng: &mut dyn rand::RngCore) -> Option<Ray> {
+        let longitude = (s - 0.5) * 2.0 * PI;
+        let latitude = (t - 0.5) * PI;
+        let local = Vec3::new(
+            latitude.cos() * longitude.sin(),
+            latitude.sin(),
+            latitude.cos() * longitude.cos(),
+        );
+        let right = longitude.cos() * self.frame.u + longitude.sin() * self.frame.w;
+        Some(self.frame.ray(
+            self.frame.origin + self.eye_offset * right,
+            local,
+            shutter_time(self.time0, self.time1, rng),
+        ))
+    }
+}
+
+// Where each eye goes in a stereo image. The left eye is on the left or on top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StereoLayout {
+    SideBySide,
+    TopBottom,
+}
+
+impl StereoLayout {
+    // The size of the image holding both eyes.
+    pub fn image_size(self, eye_width: usize, eye_height: usize) -> (usize, usize) {
+        match self {
+            StereoLayout::SideBySide => (2 * eye_width, eye_height),
+            StereoLayout::TopBottom => (eye_width, 2 * eye_height),
+        }
+    }
+}
+
+// How the two eyes of a stereo rig are aimed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StereoRig {
+    // Both eyes look in the same direction.
+    Parallel,
+    // Both eyes turn in to converge at `lookat`.
+    ToeIn,
+}
+
+// Renders two eye cameras into one image.
+pub struct StereoCamera {
+    left: Box<dyn Camera>,
+    right: Box<dyn Camera>,
+    layout: StereoLayout,
+}
+
+impl StereoCamera {
+    pub fn new(left: Box<dyn Camera>, right: Box<dyn Camera>, layout: StereoLayout) -> Self {
+        Self {
+            left,
+            right,
+            layout,
+        }
+    }
+}
+
+impl Camera for StereoCamera {
+    fn ray(&self, s: f64, t: f64, rng: &mut dyn rand::RngCore) -> Option<Ray> {
+        match self.layout {
+            StereoLayout::SideBySide if s < 0.5 => self.left.ray(2.0 * s, t, rng),
+            StereoLayout::SideBySide => self.right.ray(2.0 * s - 1.0, t, rng),
+            StereoLayout::TopBottom if t >= 0.5 => self.left.ray(s, 2.0 * t - 1.0, rng),
+            StereoLayout::TopBottom => self.right.ray(s, 2.0 * t, rng),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective,
+    // `height` is the extent of the view in world units.
+    Orthographic { height: f64 },
+    // `fov` in degrees across the image circle.
+    Fisheye { mapping: FisheyeMapping, fov: f64 },
+    Equirectangular,
+}
+
+// The camera parameters produced by scene importers. `vfov`, `aperture` and `focus_distance`
+// only apply to perspective projections.
+#[derive(Debug, Clone)]
+pub struct CameraSettings {
+    pub lookfrom: Point,
+    pub lookat: Point,
+    pub vup: Vec3,
+    pub vfov: f64, // vertical field-of-view in degrees
+    pub aspect_ratio: f64,
+    pub aperture: f64,
+    pub focus_distance: f64,
+    pub projection: Projection,
+    pub time0: f64, // shutter open time
+    pub time1: f64, // shutter close time
+}
+
+impl CameraSettings {
+    pub fn camera(&self) -> Box<dyn Camera> {
+        let (time0, time1) = (self.time0, self.time1);
+        match self.projection {
+            Projection::Perspective => Box::new(
+                PerspectiveCamera::new(
+                    self.lookfrom,
+                    self.lookat,
+                    self.vup,
+                    self.vfov,
+                    self.aspect_ratio,
+                    self.aperture,
+                    self.focus_distance,
+                )
+                .with_shutter(time0, time1),
+            ),
+            Projection::Orthographic { height } => Box::new(
+                OrthographicCamera::new(
+                    self.lookfrom,
+                    self.lookat,
+                    self.vup,
+                    height,
+                    self.aspect_ratio,
+                )
+                .with_shutter(time0, time1),
+            ),
+            Projection::Fisheye { mapping, fov } => Box::new(
+                FisheyeCamera::new(
+                    self.lookfrom,
+                    self.lookat,
+                    self.vup,
+                    mapping,
+                    fov,
+                    self.aspect_ratio,
+                )
+                .with_shutter(time0, time1),
+            ),
+            Projection::Equirectangular => Box::new(
+                EquirectangularCamera::new(self.lookfrom, self.lookat, self.vup)
+                    .with_shutter(time0, time1),
+            ),
+        }
+    }
+    // A pair of these cameras `interocular` apart. Equirectangular projections become
+    // omni-directional stereo, which has no use for `rig`.
+    pub fn stereo(&self, rig: StereoRig, interocular: f64, layout: StereoLayout) -> StereoCamera {
+        let half = interocular / 2.0;
+        let eye = |offset: f64| -> Box<dyn Camera> {
+            if self.projection == Projection::Equirectangular {
+                return Box::new(
+                    EquirectangularCamera::new(self.lookfrom, self.lookat, self.vup)
+                        .with_shutter(self.time0, self.time1)
+                        .with_eye_offset(offset),
+                );
+            }
+            let shift = offset * (self.lookat - self.lookfrom).cross(&self.vup).unit();
+            CameraSettings {
+                lookfrom: self.lookfrom + shift,
+                lookat: match rig {
+                    StereoRig::Parallel => self.lookat + shift,
+                    StereoRig::ToeIn => self.lookat,
+                },
+                ..self.clone()
+            }
+            .camera()
+        };
+        StereoCamera::new(eye(-half), eye(half), layout)
+    }
+}
+
+#[test]
+fn test_projections() {
+    let lookfrom = Point::new(0.0, 0.0, 5.0);
+    let lookat = Point::default();
+    let vup = Vec3::new(0.0, 1.0, 0.0);
+    let forward = Vec3::new(0.0, 0.0, -1.0);
+    let settings = CameraSettings {
+        lookfrom,
+        lookat,
+        vup,
+        vfov: 90.0,
+        aspect_ratio: 2.0,
+        aperture: 0.0,
+        focus_distance: 1.0,
+        projection: Projection::Perspective,
+        time0: 0.0,
+        time1: 0.0,
+    };
+    let direction = |camera: &dyn Camera, s: f64, t: f64| {
+        camera
+            .ray(s, t, &mut rand::thread_rng())
+            .map(|r| r.direction.unit())
+    };
+    let close = |a: Vec3, b: Vec3| (a - b).length() < 1e-9;
+
+    let perspective = settings.camera();
+    assert!(close(direction(&*perspective, 0.5, 0.5).unwrap(), forward));
+    // The top edge is 45 degrees up.
+    assert!(close(
+        direction(&*perspective, 0.5, 1.0).unwrap(),
+        Vec3::new(0.0, 1.0, -1.0).unit()
+    ));
+
+    let orthographic = CameraSettings {
+        projection: Projection::Orthographic { height: 4.0 },
+        ..settings.clone()
+    }
+    .camera();
+    let corner = orthographic.ray(1.0, 0.0, &mut rand::thread_rng()).unwrap();
+    assert!(close(corner.origin, Point::new(4.0, -2.0, 5.0)));
+    assert!(close(corner.direction.unit(), forward));
+
+    for &mapping in [FisheyeMapping::Equidistant, FisheyeMapping::Equisolid].iter() {
+        let fisheye = CameraSettings {
+            projection: Projection::Fisheye {
+                mapping,
+                fov: 180.0,
+            },
+            ..settings.clone()
+        }
+        .camera();
+        assert!(close(direction(&*fisheye, 0.5, 0.5).unwrap(), forward));
+        // The rim of the image circle looks sideways.
+        assert!(close(
+            direction(&*fisheye, 0.5, 1.0).unwrap(),
+            Vec3::new(0.0, 1.0, 0.0)
+        ));
+        assert!(direction(&*fisheye, 0.0, 0.0).is_none());
+    }
+
+    let panorama = CameraSettings {
+        projection: Projection::Equirectangular,
+        ..settings
+    }
+    .camera();
+    assert!(close(direction(&*panorama, 0.5, 0.5).unwrap(), forward));
+    assert!(close(
+        direction(&*panorama, 0.75, 0.5).unwrap(),
+        Vec3::new(1.0, 0.0, 0.0)
+    ));
+    assert!(close(
+        direction(&*panorama, 0.0, 0.5).unwrap(),
+        Vec3::new(0.0, 0.0, 1.0)
+    ));
+    assert!(close(
+        direction(&*panorama, 0.3, 1.0).unwrap(),
+        Vec3::new(0.0, 1.0, 0.0)
+    ));
+}
+
+#[test]
+fn test_stereo() {
+    let settings = CameraSettings {
+        lookfrom: Point::new(0.0, 0.0, 5.0),
+        lookat: Point::default(),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+        vfov: 40.0,
+        aspect_ratio: 1.0,
+        aperture: 0.0,
+        focus_distance: 1.0,
+        projection: Projection::Perspective,
+        time0: 0.0,
+        time1: 0.0,
+    };
+    let ray =
+        |camera: &StereoCamera, s: f64, t: f64| camera.ray(s, t, &mut rand::thread_rng()).unwrap();
+    let close = |a: Vec3, b: Vec3| (a - b).length() < 1e-9;
+    assert_eq!(StereoLayout::SideBySide.image_size(400, 300), (800, 300));
+    assert_eq!(StereoLayout::TopBottom.image_size(400, 300), (400, 600));
+
+    let parallel = settings.stereo(StereoRig::Parallel, 0.2, StereoLayout::SideBySide);
+    let (left, right) = (ray(&parallel, 0.25, 0.5), ray(&parallel, 0.75, 0.5));
+    assert!(close(left.origin, Point::new(-0.1, 0.0, 5.0)));
+    assert!(close(right.origin, Point::new(0.1, 0.0, 5.0)));
+    assert!(close(left.direction.unit(), right.direction.unit()));
+
+    // Toed-in eyes converge on `lookat`.
+    let toe_in = settings.stereo(StereoRig::ToeIn, 0.2, StereoLayout::TopBottom);
+    for &t in [0.25, 0.75].iter() {
+        let r = ray(&toe_in, 0.5, t);
+        assert!(close(
+            r.direction.unit(),
+            (settings.lookat - r.origin).unit()
+        ));
+    }
+    assert!(ray(&toe_in, 0.5, 0.75).origin[0] < 0.0);
+
+    // Omni-directional stereo: facing right, the left eye sits towards the original front.
+    let ods = CameraSettings {
+        projection: Projection::Equirectangular,
+        ..settings
+    }
+    .stereo(StereoRig::Parallel, 0.2, StereoLayout::TopBottom);
+    let front = ray(&ods, 0.5, 0.75);
+    assert!(close(front.origin, Point::new(-0.1, 0.0, 5.0)));
+    let side = ray(&ods, 0.75, 0.75);
+    assert!(close(side.origin, Point::new(0.0, 0.0, 4.9)));
+    assert!(close(side.direction.unit(), Vec3::new(1.0, 0.0, 0.0)));
+}
+
+#[test]
+fn test_shutter() {
+    let lookfrom = Point::new(0.0, 0.0, 5.0);
+    let lookat = Point::default();
+    let vup = Vec3::new(0.0, 1.0, 0.0);
+    let cameras: Vec<Box<dyn Camera>> = vec![
+        Box::new(PerspectiveCamera::new(
+            lookfrom, lookat, vup, 40.0, 1.0, 0.0, 1.0,
+        )),
+        Box::new(OrthographicCamera::new(lookfrom, lookat, vup, 4.0, 1.0)),
+        Box::new(FisheyeCamera::new(
+            lookfrom,
+            lookat,
+            vup,
+            FisheyeMapping::Equidistant,
+            180.0,
+            1.0,
+        )),
+        Box::new(EquirectangularCamera::new(lookfrom, lookat, vup)),
+    ];
+    let mut rng = rand::thread_rng();
+    for camera in cameras.iter() {
+        // Without a shutter interval every ray is at time 0.
+        assert_eq!(camera.ray(0.5, 0.5, &mut rng).unwrap().time, 0.0);
+    }
+    let cameras: Vec<Box<dyn Camera>> = vec![
+        Box::new(
+            PerspectiveCamera::new(lookfrom, lookat, vup, 40.0, 1.0, 0.0, 1.0)
+                .with_shutter(1.0, 2.0),
+        ),
+        Box::new(OrthographicCamera::new(lookfrom, lookat, vup, 4.0, 1.0).with_shutter(1.0, 2.0)),
+        Box::new(
+            FisheyeCamera::new(
+                lookfrom,
+                lookat,
+                vup,
+                FisheyeMapping::Equidistant,
+                180.0,
+                1.0,
+            )
+            .with_shutter(1.0, 2.0),
+        ),
+        Box::new(EquirectangularCamera::new(lookfrom, lookat, vup).with_shutter(1.0, 2.0)),
+    ];
+    for camera in cameras.iter() {
+        let times: Vec<f64> = (0..1000)
+            .map(|_| camera.ray(0.5, 0.5, &mut rng).unwrap().time)
+            .collect();
+        assert!(times.iter().all(|t| (1.0..2.0).contains(t)));
+        // Uniform over the interval: the mean is within a few standard errors of 1.5.
+        let mean = times.iter().sum::<f64>() / times.len() as f64;
+        assert!((mean - 1.5).abs() < 0.05);
+    }
+
+    // Settings from the importers pass their shutter on to every projection and to both eyes.
+    let projections = [
+        Projection::Perspective,
+        Projection::Orthographic { height: 4.0 },
+        Projection::Fisheye {
+            mapping: FisheyeMapping::Equidistant,
+            fov: 180.0,
+        },
+        Projection::Equirectangular,
+    ];
+    for &projection in projections.iter() {
+        let settings = CameraSettings {
+            lookfrom,
+            lookat,
+            vup,
+            vfov: 40.0,
+            aspect_ratio: 1.0,
+            aperture: 0.0,
+            focus_distance: 1.0,
+            projection,
+            time0: 1.0,
+            time1: 2.0,
+        };
+        let stereo = settings.stereo(StereoRig::Parallel, 0.1, StereoLayout::SideBySide);
+        for &s in [0.25, 0.75].iter() {
+            for camera in [settings.camera().as_ref(), &stereo as &dyn Camera].iter() {
+                let time = camera.ray(s, 0.5, &mut rng).unwrap().time;
+                assert!((1.0..2.0).contains(&time));
+            }
         }
     }
 }
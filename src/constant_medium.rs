@@ -0,0 +1,139 @@
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    material::{HenyeyGreenstein, Isotropic, Material},
+    ray::Ray,
+    texture::SolidColor,
+    vec3::{Color, Vec3},
+};
+use rand::Rng;
+use std::rc::Rc;
+
+// A volume of constant density filling a closed boundary, e.g. fog or smoke.
+#[derive(Clone)]
+pub struct ConstantMedium<H>
+where
+    H: Hittable,
+{
+    boundary: H,
+    neg_inv_density: f64,
+    phase_function: Rc<dyn Material>,
+}
+
+impl<H> ConstantMedium<H>
+where
+    H: Hittable,
+{
+    pub fn new(boundary: H, density: f64, albedo: Color) -> Self {
+        Self::new_with_phase_function(
+            boundary,
+            density,
+            Rc::new(Isotropic::new(Rc::new(SolidColor::new(albedo)))),
+        )
+    }
+    pub fn new_anisotropic(boundary: H, density: f64, albedo: Color, g: f64) -> Self {
+        Self::new_with_phase_function(
+            boundary,
+            density,
+            Rc::new(HenyeyGreenstein::new(Rc::new(SolidColor::new(albedo)), g)),
+        )
+    }
+    pub fn new_with_phase_function(
+        boundary: H,
+        density: f64,
+        phase_function: Rc<dyn Material>,
+    ) -> Self {
+        assert!(
+            density > 0.0 && density.is_finite(),
+            "constant medium needs a positive, finite density"
+        );
+        Self {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function,
+        }
+    }
+}
+
+impl<H> Hittable for ConstantMedium<H>
+where
+    H: Hittable,
+{
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let mut rec1 = HitRecord::default();
+        let mut rec2 = HitRecord::default();
+
+        if !self
+            .boundary
+            .hit(r, f64::NEG_INFINITY, f64::INFINITY, &mut rec1)
+        {
+            return false;
+        }
+        if !self
+            .boundary
+            .hit(r, rec1.t + 0.0001, f64::INFINITY, &mut rec2)
+        {
+            return false;
+        }
+
+        rec1.t = rec1.t.max(t_min);
+        rec2.t = rec2.t.min(t_max);
+        if rec1.t >= rec2.t {
+            return false;
+        }
+        rec1.t = rec1.t.max(0.0);
+
+        // Sample a free-flight distance and scatter if it ends before the ray leaves the volume.
+        let ray_length = r.direction.length();
+        let distance_inside_boundary = (rec2.t - rec1.t) * ray_length;
+        let hit_distance = self.neg_inv_density * rand::thread_rng().gen::<f64>().ln();
+        if hit_distance > distance_inside_boundary {
+            return false;
+        }
+
+        rec.t = rec1.t + hit_distance / ray_length;
+        rec.point = r.at(rec.t);
+        rec.normal = Vec3::new(1.0, 0.0, 0.0); // arbitrary
+        rec.front_face = true; // also arbitrary
+        rec.material = self.phase_function.clone();
+        true
+    }
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        self.boundary.bounding_box(time0, time1, output_box)
+    }
+}
+
+#[test]
+fn test_constant_medium() {
+    use crate::{sphere::Sphere, vec3::Point};
+
+    // Rays through the centre of a unit sphere cross 2 units of medium.
+    let white = Color::new(1.0, 1.0, 1.0);
+    let boundary = || {
+        let material = Rc::new(Isotropic::new(Rc::new(SolidColor::new(white))));
+        Sphere::new(Point::default(), 1.0, material)
+    };
+    let density = 0.7;
+    let medium = ConstantMedium::new(boundary(), density, white);
+    let r = Ray {
+        origin: Point::new(0.0, 0.0, -5.0),
+        direction: Vec3::new(0.0, 0.0, 2.0),
+        ..Ray::default()
+    };
+    let n = 20000;
+    let mut hits = 0;
+    for _ in 0..n {
+        let mut rec = HitRecord::default();
+        if medium.hit(&r, 0.001, f64::INFINITY, &mut rec) {
+            assert!(rec.point.length() < 1.0 + 1e-9);
+            hits += 1;
+        }
+    }
+    let expected = 1.0 - (-density * 2.0).exp();
+    assert!((hits as f64 / n as f64 - expected).abs() < 0.02);
+
+    for &density in [0.0, -1.0, f64::INFINITY, f64::NAN].iter() {
+        let result = std::panic::catch_unwind(|| ConstantMedium::new(boundary(), density, white));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{
+    aabb::{surrounding_box, Aabb},
+    hittable::{HitRecord, Hittable},
+    ray::Ray,
+    vec3::Point,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOperation {
+    fn inside(self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            Self::Union => in_left || in_right,
+            Self::Intersection => in_left && in_right,
+            Self::Difference => in_left && !in_right,
+        }
+    }
+}
+
+// Boolean combination of two closed objects. Both children must be watertight so that
+// their crossings alternate between entering and leaving.
+#[derive(Clone)]
+pub struct Csg<A, B>
+where
+    A: Hittable,
+    B: Hittable,
+{
+    operation: CsgOperation,
+    left: A,
+    right: B,
+}
+
+impl<A, B> Csg<A, B>
+where
+    A: Hittable,
+    B: Hittable,
+{
+    pub fn new(operation: CsgOperation, left: A, right: B) -> Self {
+        Self {
+            operation,
+            left,
+            right,
+        }
+    }
+    pub fn union(left: A, right: B) -> Self {
+        Self::new(CsgOperation::Union, left, right)
+    }
+    pub fn intersection(left: A, right: B) -> Self {
+        Self::new(CsgOperation::Intersection, left, right)
+    }
+    pub fn difference(left: A, right: B) -> Self {
+        Self::new(CsgOperation::Difference, left, right)
+    }
+    // Crossings of the combined boundary from t_min onwards, in increasing t.
+    fn crossings(&self, r: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord> {
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        self.left.hit_all(r, t_min, f64::INFINITY, &mut left);
+        self.right.hit_all(r, t_min, f64::INFINITY, &mut right);
+
+        // A first crossing that leaves the object means the ray starts inside it.
+        let mut in_left = left.first().is_some_and(|rec| !rec.front_face);
+        let mut in_right = right.first().is_some_and(|rec| !rec.front_face);
+        let mut inside = self.operation.inside(in_left, in_right);
+
+        let mut result = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < left.len() || j < right.len() {
+            let take_left = j >= right.len() || (i < left.len() && left[i].t <= right[j].t);
+            let mut rec = if take_left {
+                in_left = !in_left;
+                i += 1;
+                left[i - 1].clone()
+            } else {
+                in_right = !in_right;
+                j += 1;
+                right[j - 1].clone()
+            };
+            if rec.t > t_max {
+                break;
+            }
+            let now_inside = self.operation.inside(in_left, in_right);
+            if now_inside != inside {
+                inside = now_inside;
+                // Surfaces carved out of the left object face the other way.
+                if !take_left && self.operation == CsgOperation::Difference {
+                    rec.front_face = !rec.front_face;
+                }
+                result.push(rec);
+            }
+        }
+        result
+    }
+}
+
+impl<A, B> Hittable for Csg<A, B>
+where
+    A: Hittable,
+    B: Hittable,
+{
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        match self.crossings(r, t_min, t_max).into_iter().next() {
+            Some(first) => {
+                *rec = first;
+                true
+            }
+            None => false,
+        }
+    }
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        let mut left_box = Aabb::default();
+        let mut right_box = Aabb::default();
+        let has_left = self.left.bounding_box(time0, time1, &mut left_box);
+        let has_right = self.right.bounding_box(time0, time1, &mut right_box);
+        match self.operation {
+            CsgOperation::Union => {
+                if !has_left || !has_right {
+                    return false;
+                }
+                *output_box = surrounding_box(&left_box, &right_box);
+            }
+            CsgOperation::Intersection => {
+                *output_box = match (has_left, has_right) {
+                    (true, true) => {
+                        let (lo, hi) = (left_box.min(), left_box.max());
+                        let (rlo, rhi) = (right_box.min(), right_box.max());
+                        Aabb::new(
+                            Point::new(lo[0].max(rlo[0]), lo[1].max(rlo[1]), lo[2].max(rlo[2])),
+                            Point::new(hi[0].min(rhi[0]), hi[1].min(rhi[1]), hi[2].min(rhi[2])),
+                        )
+                    }
+                    (true, false) => left_box,
+                    (false, true) => right_box,
+                    (false, false) => return false,
+                };
+            }
+            CsgOperation::Difference => {
+                if !has_left {
+                    return false;
+                }
+                *output_box = left_box;
+            }
+        }
+        true
+    }
+    fn hit_all(&self, r: &Ray, t_min: f64, t_max: f64, hits: &mut Vec<HitRecord>) {
+        hits.extend(self.crossings(r, t_min, t_max));
+    }
+}
+
+#[test]
+fn test_csg_difference() {
+    use crate::{material::Lambertian, sphere::Sphere, vec3::Color};
+    use std::rc::Rc;
+    let material = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let outer = Sphere::new(Point::new(0.0, 0.0, 0.0), 2.0, material.clone());
+    let inner = Sphere::new(Point::new(0.0, 0.0, 0.0), 1.0, material);
+    let shell = Csg::difference(outer, inner);
+    let r = Ray {
+        origin: Point::new(0.0, 0.0, -5.0),
+        direction: crate::vec3::Vec3::new(0.0, 0.0, 1.0),
+        ..Default::default()
+    };
+    let mut hits = Vec::new();
+    shell.hit_all(&r, 0.001, f64::INFINITY, &mut hits);
+    let ts: Vec<f64> = hits.iter().map(|rec| rec.t).collect();
+    let faces: Vec<bool> = hits.iter().map(|rec| rec.front_face).collect();
+    assert_eq!(ts.len(), 4);
+    for (t, expected) in ts.iter().zip(&[3.0, 4.0, 6.0, 7.0]) {
+        assert!((t - expected).abs() < 1e-6);
+    }
+    assert_eq!(faces, vec![true, false, true, false]);
+    // Starting inside the hole, the first boundary is the inner sphere entering the shell.
+    let mut rec = HitRecord::default();
+    assert!(shell.hit(&r, 5.0, f64::INFINITY, &mut rec));
+    assert!((rec.t - 6.0).abs() < 1e-6 && rec.front_face);
+}
+
+#[test]
+fn test_csg_union_intersection() {
+    use crate::{material::Lambertian, sphere::Sphere, vec3::Color, vec3::Vec3};
+    use std::rc::Rc;
+    let material = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let left = Sphere::new(Point::new(-0.5, 0.0, 0.0), 1.0, material.clone());
+    let right = Sphere::new(Point::new(0.5, 0.0, 0.0), 1.0, material);
+    let union = Csg::union(left.clone(), right.clone());
+    let intersection = Csg::intersection(left, right);
+    let first_hit = |object: &dyn Hittable, origin: Point, direction: Vec3| {
+        let r = Ray {
+            origin,
+            direction,
+            ..Default::default()
+        };
+        let mut rec = HitRecord::default();
+        if object.hit(&r, 0.001, f64::INFINITY, &mut rec) {
+            Some((rec.t, rec.front_face))
+        } else {
+            None
+        }
+    };
+    let close = |hit: Option<(f64, bool)>, t: f64, front_face: bool| {
+        hit.is_some_and(|hit| (hit.0 - t).abs() < 1e-6 && hit.1 == front_face)
+    };
+
+    // Along the axis the spheres are entered at x = -1.5 and -0.5 and left at 0.5 and 1.5.
+    let origin = Point::new(-5.0, 0.0, 0.0);
+    let axis = Vec3::new(1.0, 0.0, 0.0);
+    assert!(close(first_hit(&union, origin, axis), 3.5, true));
+    assert!(close(first_hit(&intersection, origin, axis), 4.5, true));
+    let mut hits = Vec::new();
+    union.hit_all(
+        &Ray {
+            origin,
+            direction: axis,
+            ..Default::default()
+        },
+        0.001,
+        f64::INFINITY,
+        &mut hits,
+    );
+    assert_eq!(hits.len(), 2);
+    assert!((hits[1].t - 6.5).abs() < 1e-6 && !hits[1].front_face);
+
+    // At x = -1.2 a ray crosses the left sphere only.
+    let origin = Point::new(-1.2, 0.0, -5.0);
+    let across = Vec3::new(0.0, 0.0, 1.0);
+    assert!(first_hit(&union, origin, across).is_some());
+    assert!(first_hit(&intersection, origin, across).is_none());
+
+    // Starting inside the left sphere only, the union is left where the right sphere ends
+    // and the intersection is entered where the right sphere begins.
+    let origin = Point::new(-1.0, 0.0, 0.0);
+    assert!(close(first_hit(&union, origin, axis), 2.5, false));
+    assert!(close(first_hit(&intersection, origin, axis), 0.5, true));
+}
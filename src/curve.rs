@@ -0,0 +1,364 @@
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    onb::Onb,
+    ray::Ray,
+    vec3::{Point, Vec3},
+};
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy)]
+pub enum CurveType {
+    // A flat strip that always faces the incoming ray; cheap stand-in for thin hair.
+    Flat,
+    // A strip oriented by normals at either end, interpolated along the curve.
+    Ribbon(Vec3, Vec3),
+    // A round tube. Rays starting on or inside it pass through, as the hair BSDF expects.
+    Cylinder,
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    (1.0 - t) * a + t * b
+}
+
+fn eval_bezier(cp: &[Point; 4], u: f64) -> (Point, Vec3) {
+    let cp1 = [
+        cp[0] + u * (cp[1] - cp[0]),
+        cp[1] + u * (cp[2] - cp[1]),
+        cp[2] + u * (cp[3] - cp[2]),
+    ];
+    let cp2 = [
+        cp1[0] + u * (cp1[1] - cp1[0]),
+        cp1[1] + u * (cp1[2] - cp1[1]),
+    ];
+    let derivative = if (cp2[1] - cp2[0]).length_squared() > 0.0 {
+        3.0 * (cp2[1] - cp2[0])
+    } else {
+        // The derivative vanishes at a doubled end point; the chord still points the right way.
+        cp[3] - cp[0]
+    };
+    (cp2[0] + u * (cp2[1] - cp2[0]), derivative)
+}
+
+fn split_bezier(cp: &[Point; 4]) -> ([Point; 4], [Point; 4]) {
+    let mid = |a: Point, b: Point| (a + b) * 0.5;
+    let (a, b, c) = (mid(cp[0], cp[1]), mid(cp[1], cp[2]), mid(cp[2], cp[3]));
+    let (d, e) = (mid(a, b), mid(b, c));
+    let f = mid(d, e);
+    ([cp[0], a, d, f], [f, e, c, cp[3]])
+}
+
+// The control points of the part of the curve between `u0` and `u1`.
+fn blossom_segment(cp: &[Point; 4], u0: f64, u1: f64) -> [Point; 4] {
+    let blossom = |a: f64, b: f64, c: f64| {
+        let l = |p: Point, q: Point, t: f64| p + t * (q - p);
+        let p0 = [l(cp[0], cp[1], a), l(cp[1], cp[2], a), l(cp[2], cp[3], a)];
+        let p1 = [l(p0[0], p0[1], b), l(p0[1], p0[2], b)];
+        l(p1[0], p1[1], c)
+    };
+    [
+        blossom(u0, u0, u0),
+        blossom(u0, u0, u1),
+        blossom(u0, u1, u1),
+        blossom(u1, u1, u1),
+    ]
+}
+
+// A cubic Bézier curve whose width varies linearly from one end to the other, intersected by
+// recursive subdivision in a coordinate system where the ray runs along +z.
+#[derive(Clone)]
+pub struct Curve {
+    control_points: [Point; 4],
+    widths: (f64, f64),
+    curve_type: CurveType,
+    // The part of the parent curve this segment covers, so u runs over the whole curve.
+    u_range: (f64, f64),
+    max_depth: u32,
+    material: Rc<dyn Material>,
+}
+
+impl Curve {
+    pub fn new(
+        control_points: [Point; 4],
+        width0: f64,
+        width1: f64,
+        curve_type: CurveType,
+        material: Rc<dyn Material>,
+    ) -> Self {
+        Self::new_segment(
+            control_points,
+            (width0, width1),
+            curve_type,
+            (0.0, 1.0),
+            material,
+        )
+    }
+    fn new_segment(
+        control_points: [Point; 4],
+        widths: (f64, f64),
+        curve_type: CurveType,
+        u_range: (f64, f64),
+        material: Rc<dyn Material>,
+    ) -> Self {
+        // Enough subdivisions for the segments to stay within a fraction of the width of the
+        // curve, from the bound on a Bézier curve's distance to its control polygon.
+        let cp = &control_points;
+        let l0 = (0..2)
+            .flat_map(|i| (0..3).map(move |a| (cp[i][a] - 2.0 * cp[i + 1][a] + cp[i + 2][a]).abs()))
+            .fold(0.0, f64::max);
+        let eps = 0.05 * widths.0.max(widths.1);
+        let max_depth = if eps > 0.0 && l0 > 0.0 {
+            ((2f64.sqrt() * 6.0 * l0 / (8.0 * eps)).log2() / 2.0).clamp(0.0, 10.0) as u32
+        } else {
+            0
+        };
+        Self {
+            control_points,
+            widths,
+            curve_type,
+            u_range,
+            max_depth,
+            material,
+        }
+    }
+    // Splits the curve into pieces with tighter bounds, to be put into a `Bvh` together.
+    pub fn split(&self, count: usize) -> Vec<Self> {
+        (0..count)
+            .map(|i| {
+                let s0 = i as f64 / count as f64;
+                let s1 = (i + 1) as f64 / count as f64;
+                Self::new_segment(
+                    blossom_segment(&self.control_points, s0, s1),
+                    self.widths,
+                    self.curve_type,
+                    (
+                        lerp(s0, self.u_range.0, self.u_range.1),
+                        lerp(s1, self.u_range.0, self.u_range.1),
+                    ),
+                    self.material.clone(),
+                )
+            })
+            .collect()
+    }
+    fn width(&self, u: f64) -> f64 {
+        lerp(u, self.widths.0, self.widths.1)
+    }
+    fn ribbon_normal(&self, u: f64) -> Option<Vec3> {
+        match self.curve_type {
+            CurveType::Ribbon(n0, n1) => {
+                let n = (1.0 - u) * n0.unit() + u * n1.unit();
+                Some(if n.is_near_zero() {
+                    n0.unit()
+                } else {
+                    n.unit()
+                })
+            }
+            _ => None,
+        }
+    }
+    // Finds the closest hit on the part of the curve between `u0` and `u1` (in the parameter
+    // of the whole curve), with control points `cp` in ray space. Returns (z, u, v).
+    fn recursive_hit(
+        &self,
+        cp: &[Point; 4],
+        direction: &Vec3,
+        z_min: f64,
+        z_max: f64,
+        (u0, u1): (f64, f64),
+        depth: u32,
+    ) -> Option<(f64, f64, f64)> {
+        let max_width = self.width(u0).max(self.width(u1));
+        let bounds = Aabb::from_points(cp).padded(0.5 * max_width);
+        if bounds.min()[0] > 0.0
+            || bounds.max()[0] < 0.0
+            || bounds.min()[1] > 0.0
+            || bounds.max()[1] < 0.0
+            || bounds.max()[2] < z_min
+            || bounds.min()[2] > z_max
+        {
+            return None;
+        }
+        if depth > 0 {
+            let (first, second) = split_bezier(cp);
+            let u_mid = 0.5 * (u0 + u1);
+            let near = self.recursive_hit(&first, direction, z_min, z_max, (u0, u_mid), depth - 1);
+            let z_max = near.map_or(z_max, |(z, _, _)| z);
+            let far = self.recursive_hit(&second, direction, z_min, z_max, (u_mid, u1), depth - 1);
+            return far.or(near);
+        }
+
+        // Reject points beyond the perpendiculars at either end, so neighbouring segments do
+        // not both report the same hit.
+        let edge = (cp[1][1] - cp[0][1]) * -cp[0][1] + cp[0][0] * (cp[0][0] - cp[1][0]);
+        if edge < 0.0 {
+            return None;
+        }
+        let edge = (cp[2][1] - cp[3][1]) * -cp[3][1] + cp[3][0] * (cp[3][0] - cp[2][0]);
+        if edge < 0.0 {
+            return None;
+        }
+        let segment = (cp[3][0] - cp[0][0], cp[3][1] - cp[0][1]);
+        let denominator = segment.0 * segment.0 + segment.1 * segment.1;
+        if denominator == 0.0 {
+            return None;
+        }
+        let w = ((-cp[0][0] * segment.0 - cp[0][1] * segment.1) / denominator).clamp(0.0, 1.0);
+        let u = lerp(w, u0, u1);
+        let mut hit_width = self.width(u);
+        if let Some(normal) = self.ribbon_normal(u) {
+            hit_width *= normal.dot(direction).abs();
+        }
+        let (pc, dpcdw) = eval_bezier(cp, w);
+        let distance2 = pc[0] * pc[0] + pc[1] * pc[1];
+        let radius = 0.5 * hit_width;
+        if distance2 > radius * radius {
+            return None;
+        }
+        let z = match self.curve_type {
+            CurveType::Cylinder => pc[2] - (radius * radius - distance2).sqrt(),
+            _ => pc[2],
+        };
+        if z < z_min || z > z_max {
+            return None;
+        }
+        let distance = distance2.sqrt();
+        let edge = dpcdw[0] * -pc[1] + pc[0] * dpcdw[1];
+        let v = if edge > 0.0 {
+            0.5 + distance / hit_width
+        } else {
+            0.5 - distance / hit_width
+        };
+        Some((z, u, v))
+    }
+}
+
+impl Hittable for Curve {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let length = r.direction.length();
+        let frame = Onb::new_from_w(&r.direction);
+        let mut cp = [Point::default(); 4];
+        for (p, q) in cp.iter_mut().zip(self.control_points.iter()) {
+            *p = frame.to_local(&(*q - r.origin));
+        }
+        let direction = r.direction / length;
+        let (z, u, v) = match self.recursive_hit(
+            &cp,
+            &direction,
+            t_min * length,
+            t_max * length,
+            self.u_range,
+            self.max_depth,
+        ) {
+            Some(hit) => hit,
+            None => return false,
+        };
+
+        let local_u = (u - self.u_range.0) / (self.u_range.1 - self.u_range.0);
+        let (center, tangent) = eval_bezier(&self.control_points, local_u);
+        rec.t = z / length;
+        rec.point = r.at(rec.t);
+        rec.u = u;
+        rec.v = v;
+        rec.tangent = Some(tangent.unit());
+        rec.vertex_color = None;
+        rec.material = self.material.clone();
+        match self.curve_type {
+            CurveType::Flat => {
+                rec.front_face = true;
+                rec.normal = -direction;
+            }
+            CurveType::Ribbon(_, _) => {
+                let normal = self.ribbon_normal(u).unwrap_or(-direction);
+                rec.set_face_normal(r, &normal);
+            }
+            CurveType::Cylinder => {
+                let tangent = tangent.unit();
+                let offset = rec.point - center;
+                let normal = offset - offset.dot(&tangent) * tangent;
+                rec.front_face = true;
+                rec.normal = if normal.is_near_zero() {
+                    -direction
+                } else {
+                    normal.unit()
+                };
+            }
+        }
+        true
+    }
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        let max_width = self.width(self.u_range.0).max(self.width(self.u_range.1));
+        *output_box = Aabb::from_points(&self.control_points).padded(0.5 * max_width);
+        true
+    }
+}
+
+#[test]
+fn test_curve_hit() {
+    use crate::material::Lambertian;
+    use crate::vec3::Color;
+
+    let material = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let control_points = [
+        Point::new(-1.0, 0.0, 0.0),
+        Point::new(-0.3, 0.5, 0.0),
+        Point::new(0.3, -0.5, 0.0),
+        Point::new(1.0, 0.0, 0.0),
+    ];
+    let r = Ray {
+        origin: Point::new(0.0, 0.0, -5.0),
+        direction: Vec3::new(0.0, 0.0, 2.0),
+        ..Default::default()
+    };
+    let mut rec = HitRecord::default();
+
+    let flat = Curve::new(control_points, 0.2, 0.2, CurveType::Flat, material.clone());
+    assert!(flat.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 2.5).abs() < 1e-6);
+    assert!((rec.u - 0.5).abs() < 1e-3);
+    assert!((rec.normal - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-9);
+
+    // The tube's surface is in front of its centre line, and split pieces find the same hit.
+    let tube = Curve::new(
+        control_points,
+        0.2,
+        0.2,
+        CurveType::Cylinder,
+        material.clone(),
+    );
+    assert!(tube.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.point[2] + 0.1).abs() < 1e-3);
+    // The ray crosses the middle of the curve, so only the middle piece reports it.
+    let hits: Vec<usize> = tube
+        .split(3)
+        .iter()
+        .enumerate()
+        .filter(|(_, piece)| piece.hit(&r, 0.001, f64::INFINITY, &mut rec))
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(hits, vec![1]);
+    assert!(tube.split(3)[1].hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.point[2] + 0.1).abs() < 1e-3);
+    assert!((rec.u - 0.5).abs() < 1e-3);
+
+    // Too thin where the ray passes, and a ribbon seen edge on.
+    let thin = Curve::new(control_points, 0.2, 0.0, CurveType::Flat, material.clone());
+    let off_center = Ray {
+        origin: Point::new(0.9, 0.08, -5.0),
+        ..r
+    };
+    assert!(!thin.hit(&off_center, 0.001, f64::INFINITY, &mut rec));
+    let ribbon = Curve::new(
+        control_points,
+        0.2,
+        0.2,
+        CurveType::Ribbon(Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+        material,
+    );
+    let above = Ray {
+        origin: Point::new(0.0, 0.01, -5.0),
+        ..r
+    };
+    assert!(flat.hit(&above, 0.001, f64::INFINITY, &mut rec));
+    assert!(!ribbon.hit(&above, 0.001, f64::INFINITY, &mut rec));
+}
@@ -0,0 +1,193 @@
+use crate::{mesh::TriangleMesh, texture::Texture};
+use std::collections::HashMap;
+
+// Edge splitting stops after this many rounds even if some edges are still too long.
+const MAX_ROUNDS: usize = 16;
+// Nor does it start a round that could take the mesh past this many triangles; each round
+// at most quadruples them.
+const MAX_TRIANGLES: usize = 1 << 22;
+
+impl TriangleMesh {
+    // A copy of the mesh with every vertex moved along its normal by `scale` times the first
+    // channel of `height`, looked up at the vertex's (u, v) and position. Triangles are split
+    // first until no edge is longer than `max_edge_length`, which bounds how fine the detail
+    // can be and how many triangles come out. Triangles must share vertices to stay joined.
+    // Panics unless `max_edge_length` is positive and finite.
+    pub fn displaced(&self, height: &dyn Texture, scale: f64, max_edge_length: f64) -> Self {
+        self.displaced_within(height, scale, max_edge_length, MAX_TRIANGLES)
+    }
+    fn displaced_within(
+        &self,
+        height: &dyn Texture,
+        scale: f64,
+        max_edge_length: f64,
+        max_triangles: usize,
+    ) -> Self {
+        assert!(
+            max_edge_length > 0.0 && max_edge_length.is_finite(),
+            "displacement needs a positive, finite edge length"
+        );
+        let mut mesh = self.clone();
+        mesh.validate();
+        if mesh.normals.is_empty() {
+            mesh.compute_normals();
+        }
+        for _ in 0..MAX_ROUNDS {
+            if mesh.indices.len() > max_triangles / 4 || !mesh.split_long_edges(max_edge_length) {
+                break;
+            }
+        }
+        for i in 0..mesh.positions.len() {
+            let (u, v) = mesh.uvs.get(i).copied().unwrap_or((0.0, 0.0));
+            let h = height.value(u, v, &mesh.positions[i])[0];
+            mesh.positions[i] += scale * h * mesh.normals[i];
+        }
+        mesh.compute_normals();
+        mesh
+    }
+    fn add_midpoint(&mut self, a: usize, b: usize) -> usize {
+        self.positions
+            .push(0.5 * (self.positions[a] + self.positions[b]));
+        if !self.normals.is_empty() {
+            let n = self.normals[a] + self.normals[b];
+            self.normals.push(if n.is_near_zero() {
+                self.normals[a]
+            } else {
+                n.unit()
+            });
+        }
+        if !self.colors.is_empty() {
+            self.colors.push(0.5 * (self.colors[a] + self.colors[b]));
+        }
+        if !self.uvs.is_empty() {
+            let (ua, va) = self.uvs[a];
+            let (ub, vb) = self.uvs[b];
+            self.uvs.push((0.5 * (ua + ub), 0.5 * (va + vb)));
+        }
+        self.positions.len() - 1
+    }
+    // Splits every edge longer than `max_length` at its midpoint. Split points are shared by
+    // the triangles on either side, so no cracks open up. Returns whether anything was split.
+    fn split_long_edges(&mut self, max_length: f64) -> bool {
+        let mut midpoints = HashMap::new();
+        for t in 0..self.indices.len() {
+            let triangle = self.indices[t];
+            for k in 0..3 {
+                let (a, b) = (triangle[k], triangle[(k + 1) % 3]);
+                let key = (a.min(b), a.max(b));
+                if !midpoints.contains_key(&key)
+                    && (self.positions[a] - self.positions[b]).length() > max_length
+                {
+                    let m = self.add_midpoint(a, b);
+                    midpoints.insert(key, m);
+                }
+            }
+        }
+        if midpoints.is_empty() {
+            return false;
+        }
+
+        let mut indices = Vec::with_capacity(4 * self.indices.len());
+        for &v in self.indices.iter() {
+            let mid = |k: usize| {
+                let (a, b) = (v[k], v[(k + 1) % 3]);
+                midpoints.get(&(a.min(b), a.max(b))).copied()
+            };
+            let m = [mid(0), mid(1), mid(2)];
+            match m.iter().filter(|m| m.is_some()).count() {
+                0 => indices.push(v),
+                1 => {
+                    let k = m.iter().position(|m| m.is_some()).unwrap();
+                    let (x, next, opposite) = (m[k].unwrap(), v[(k + 1) % 3], v[(k + 2) % 3]);
+                    indices.push([v[k], x, opposite]);
+                    indices.push([x, next, opposite]);
+                }
+                2 => {
+                    // The corner between the two split edges is cut off; what remains is a
+                    // quad, split along its shorter diagonal.
+                    let k = m.iter().position(|m| m.is_none()).unwrap();
+                    let (a, b, c) = (v[k], v[(k + 1) % 3], v[(k + 2) % 3]);
+                    let (x, y) = (m[(k + 1) % 3].unwrap(), m[(k + 2) % 3].unwrap());
+                    indices.push([y, x, c]);
+                    let p = &self.positions;
+                    if (p[a] - p[x]).length_squared() <= (p[b] - p[y]).length_squared() {
+                        indices.push([a, b, x]);
+                        indices.push([a, x, y]);
+                    } else {
+                        indices.push([a, b, y]);
+                        indices.push([b, x, y]);
+                    }
+                }
+                _ => {
+                    let (ab, bc, ca) = (m[0].unwrap(), m[1].unwrap(), m[2].unwrap());
+                    indices.push([v[0], ab, ca]);
+                    indices.push([v[1], bc, ab]);
+                    indices.push([v[2], ca, bc]);
+                    indices.push([ab, bc, ca]);
+                }
+            }
+        }
+        self.indices = indices;
+        true
+    }
+}
+
+#[test]
+fn test_displaced() {
+    use crate::{
+        texture::SolidColor,
+        vec3::{Point, Vec3},
+    };
+
+    let square = TriangleMesh {
+        positions: vec![
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(1.0, 2.0, 0.0),
+            Point::new(0.0, 2.0, 0.0),
+        ],
+        indices: vec![[0, 1, 2], [0, 2, 3]],
+        uvs: vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)],
+        ..TriangleMesh::default()
+    };
+    let mesh = square.displaced(&SolidColor::new_scalar(0.5), 0.2, 0.3);
+    assert!(mesh.indices.len() > 2 * 16);
+    assert_eq!(mesh.uvs.len(), mesh.positions.len());
+    for &[a, b, c] in mesh.indices.iter() {
+        for &(i, j) in [(a, b), (b, c), (c, a)].iter() {
+            assert!((mesh.positions[i] - mesh.positions[j]).length() <= 0.3);
+        }
+        // Winding is kept, so the normals still face +z.
+        let n =
+            (mesh.positions[b] - mesh.positions[a]).cross(&(mesh.positions[c] - mesh.positions[a]));
+        assert!(n[2] > 0.0);
+    }
+    for (p, n) in mesh.positions.iter().zip(mesh.normals.iter()) {
+        assert!((p[2] - 0.1).abs() < 1e-12);
+        assert!((*n - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-12);
+    }
+}
+
+#[test]
+fn test_displaced_budget() {
+    use crate::{texture::SolidColor, vec3::Point};
+
+    let triangle = TriangleMesh {
+        positions: vec![
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(0.0, 1.0, 0.0),
+        ],
+        indices: vec![[0, 1, 2]],
+        ..TriangleMesh::default()
+    };
+    let height = SolidColor::new_scalar(0.0);
+    // A budget far finer than the cap allows stops at the last round that fits under it.
+    let mesh = triangle.displaced_within(&height, 1.0, 1e-9, 1000);
+    assert!(mesh.indices.len() <= 1000);
+    assert_eq!(mesh.indices.len(), 256);
+    for &budget in [0.0, -1.0, f64::NAN, f64::INFINITY].iter() {
+        let result = std::panic::catch_unwind(|| triangle.displaced(&height, 1.0, budget));
+        assert!(result.is_err());
+    }
+}
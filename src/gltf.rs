@@ -0,0 +1,462 @@
+use crate::{
+    camera::{self, CameraSettings},
+    hittable::HitRecord,
+    material::{Material, Principled},
+    mesh::{Mesh, TriangleMesh},
+    scene::{Light, Scene},
+    texture::{srgb_to_linear, ImageTexture, SolidColor, Texture, VertexColor},
+    transform::{Matrix4, Transform},
+    vec3::{Color, Point, Vec3},
+};
+use ::gltf::{
+    camera::Projection, image::Format, khr_lights_punctual::Kind, mesh::Mode, texture, Document,
+    Node,
+};
+use std::{collections::HashMap, io, path::Path, rc::Rc};
+
+// glTF lights are photometric; dividing by the efficacy of 555nm light gives radiometric units.
+const LUMENS_PER_WATT: f64 = 683.0;
+
+impl Scene {
+    // Loads a .gltf (with external or embedded buffers) or a .glb file.
+    pub fn load_gltf<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let (document, buffers, images) = ::gltf::import(path).map_err(to_io_error)?;
+        Ok(Importer::new(&buffers, &images).import(&document))
+    }
+    // Reads a self-contained .glb or .gltf held in memory.
+    pub fn read_gltf(data: &[u8]) -> io::Result<Self> {
+        let (document, buffers, images) = ::gltf::import_slice(data).map_err(to_io_error)?;
+        Ok(Importer::new(&buffers, &images).import(&document))
+    }
+}
+
+fn to_io_error(error: ::gltf::Error) -> io::Error {
+    match error {
+        ::gltf::Error::Io(error) => error,
+        error => io::Error::new(io::ErrorKind::InvalidData, error.to_string()),
+    }
+}
+
+struct Importer<'a> {
+    buffers: &'a [::gltf::buffer::Data],
+    images: &'a [::gltf::image::Data],
+    textures: HashMap<(usize, bool), Rc<dyn Texture>>,
+    materials: HashMap<(Option<usize>, bool), Rc<dyn Material>>,
+    scene: Scene,
+}
+
+impl<'a> Importer<'a> {
+    fn new(buffers: &'a [::gltf::buffer::Data], images: &'a [::gltf::image::Data]) -> Self {
+        Self {
+            buffers,
+            images,
+            textures: HashMap::new(),
+            materials: HashMap::new(),
+            scene: Scene::default(),
+        }
+    }
+    fn import(mut self, document: &Document) -> Scene {
+        match document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+        {
+            Some(scene) => {
+                for node in scene.nodes() {
+                    self.visit(&node, &Transform::identity());
+                }
+            }
+            None => self.warn("file contains no scene".to_string()),
+        }
+        self.scene
+    }
+    fn warn(&mut self, message: String) {
+        if !self.scene.warnings.contains(&message) {
+            self.scene.warnings.push(message);
+        }
+    }
+    fn visit(&mut self, node: &Node, parent: &Transform) {
+        // glTF matrices are column major.
+        let columns = node.transform().matrix();
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = f64::from(columns[j][i]);
+            }
+        }
+        let local = match Transform::new(Matrix4::new(m)) {
+            Some(local) => local,
+            None => {
+                self.warn(format!("node {} has a singular transform", node.index()));
+                return;
+            }
+        };
+        let global = local.then(parent);
+        if let Some(mesh) = node.mesh() {
+            for primitive in mesh.primitives() {
+                self.primitive(&primitive, &global);
+            }
+        }
+        if let Some(camera) = node.camera() {
+            self.camera(&camera, &global);
+        }
+        if let Some(light) = node.light() {
+            self.light(&light, &global);
+        }
+        for child in node.children() {
+            self.visit(&child, &global);
+        }
+    }
+    fn primitive(&mut self, primitive: &::gltf::Primitive, transform: &Transform) {
+        if primitive.mode() != Mode::Triangles {
+            self.warn(format!(
+                "{:?} primitives are not supported",
+                primitive.mode()
+            ));
+            return;
+        }
+        let buffers = self.buffers;
+        let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+        let positions: Vec<Point> = match reader.read_positions() {
+            Some(positions) => positions.map(vector).collect(),
+            None => return,
+        };
+        let indices: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        let mut mesh = TriangleMesh {
+            indices: indices
+                .chunks_exact(3)
+                .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+                .collect(),
+            positions,
+            normals: reader
+                .read_normals()
+                .map(|normals| normals.map(vector).collect())
+                .unwrap_or_default(),
+            colors: reader
+                .read_colors(0)
+                .map(|colors| colors.into_rgb_f32().map(vector).collect())
+                .unwrap_or_default(),
+            // glTF puts v = 0 at the top of the image.
+            uvs: reader
+                .read_tex_coords(0)
+                .map(|uvs| {
+                    uvs.into_f32()
+                        .map(|[u, v]| (f64::from(u), 1.0 - f64::from(v)))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        mesh.transform(transform);
+        let gltf_material = primitive.material();
+        let material = self.material(&gltf_material, !mesh.colors.is_empty());
+        // Single-sided materials ask for back-face culling. Refraction needs the back faces
+        // of closed meshes to get out again, so transmissive materials keep them.
+        let mesh = if gltf_material.double_sided() || gltf_material.transmission().is_some() {
+            Mesh::new(mesh, material)
+        } else {
+            Mesh::new_single_sided(mesh, material)
+        };
+        self.scene.objects.push(Box::new(mesh));
+    }
+    fn material(&mut self, material: &::gltf::Material, vertex_colors: bool) -> Rc<dyn Material> {
+        let key = (material.index(), vertex_colors);
+        if let Some(material) = self.materials.get(&key) {
+            return material.clone();
+        }
+        if material.alpha_mode() != ::gltf::material::AlphaMode::Opaque {
+            self.warn("alpha blending and masking are ignored".to_string());
+        }
+        if material.normal_texture().is_some() {
+            self.warn("normal maps are ignored".to_string());
+        }
+        let pbr = material.pbr_metallic_roughness();
+        let factor = pbr.base_color_factor();
+        let mut base_color = self.scaled(
+            pbr.base_color_texture(),
+            true,
+            vector([factor[0], factor[1], factor[2]]),
+        );
+        if vertex_colors {
+            let white = Rc::new(SolidColor::new_scalar(1.0));
+            base_color = Rc::new(Product(Rc::new(VertexColor::new(white)), base_color));
+        }
+        // Roughness is stored in the green channel and metalness in the blue channel.
+        let metallic_roughness = pbr
+            .metallic_roughness_texture()
+            .and_then(|info| self.texture(&info, false));
+        let channel = |channel: usize, factor: f32| -> Rc<dyn Texture> {
+            match &metallic_roughness {
+                Some(texture) => Rc::new(Channel {
+                    texture: texture.clone(),
+                    channel,
+                    factor: f64::from(factor),
+                }),
+                None => Rc::new(SolidColor::new_scalar(f64::from(factor))),
+            }
+        };
+        let mut principled = Principled::new(base_color)
+            .with_metallic(channel(2, pbr.metallic_factor()))
+            .with_roughness(channel(1, pbr.roughness_factor()));
+        if let Some(transmission) = material.transmission() {
+            if transmission.transmission_texture().is_some() {
+                self.warn("transmission textures are ignored".to_string());
+            }
+            principled = principled.with_transmission(
+                Rc::new(SolidColor::new_scalar(f64::from(
+                    transmission.transmission_factor(),
+                ))),
+                f64::from(material.ior().unwrap_or(1.5)),
+            );
+        }
+        let emissive = vector(material.emissive_factor())
+            * f64::from(material.emissive_strength().unwrap_or(1.0));
+        if emissive.length_squared() > 0.0 {
+            let emission = self.scaled(material.emissive_texture(), true, emissive);
+            principled = principled.with_emission(emission);
+        }
+        let principled: Rc<dyn Material> = Rc::new(principled);
+        self.materials.insert(key, principled.clone());
+        principled
+    }
+    // A texture multiplied by a constant color, or just the color without a texture.
+    fn scaled(
+        &mut self,
+        info: Option<texture::Info>,
+        srgb: bool,
+        factor: Color,
+    ) -> Rc<dyn Texture> {
+        let constant = Rc::new(SolidColor::new(factor));
+        match info.and_then(|info| self.texture(&info, srgb)) {
+            Some(texture) => Rc::new(Product(texture, constant)),
+            None => constant,
+        }
+    }
+    fn texture(&mut self, info: &texture::Info, srgb: bool) -> Option<Rc<dyn Texture>> {
+        if info.tex_coord() != 0 {
+            self.warn("only the first texture coordinate set is used".to_string());
+        }
+        let index = info.texture().source().index();
+        if let Some(texture) = self.textures.get(&(index, srgb)) {
+            return Some(texture.clone());
+        }
+        let image = self.images.get(index)?;
+        let (width, height) = (image.width as usize, image.height as usize);
+        let (channels, bytes) = match image.format {
+            Format::R8 => (1, 1),
+            Format::R8G8 => (2, 1),
+            Format::R8G8B8 => (3, 1),
+            Format::R8G8B8A8 => (4, 1),
+            Format::R16 => (1, 2),
+            Format::R16G16 => (2, 2),
+            Format::R16G16B16 => (3, 2),
+            Format::R16G16B16A16 => (4, 2),
+            Format::R32G32B32FLOAT => (3, 4),
+            Format::R32G32B32A32FLOAT => (4, 4),
+        };
+        let float = matches!(
+            image.format,
+            Format::R32G32B32FLOAT | Format::R32G32B32A32FLOAT
+        );
+        let sample = |offset: usize| -> f64 {
+            let b = &image.pixels[offset..offset + bytes];
+            match (bytes, float) {
+                (1, _) => f64::from(b[0]) / 255.0,
+                (2, _) => f64::from(u16::from_ne_bytes([b[0], b[1]])) / 65535.0,
+                _ => f64::from(f32::from_ne_bytes([b[0], b[1], b[2], b[3]])),
+            }
+        };
+        let pixels = (0..width * height)
+            .map(|i| {
+                let base = i * channels * bytes;
+                let mut c = Color::default();
+                for a in 0..3 {
+                    // Gray images repeat their only channel; two-channel images leave blue at zero.
+                    let source = if channels == 1 { 0 } else { a };
+                    if source < channels {
+                        c[a] = sample(base + source * bytes);
+                        if srgb && !float {
+                            c[a] = srgb_to_linear(c[a]);
+                        }
+                    }
+                }
+                c
+            })
+            .collect();
+        let texture: Rc<dyn Texture> = Rc::new(ImageTexture::new(width, height, pixels));
+        self.textures.insert((index, srgb), texture.clone());
+        Some(texture)
+    }
+    fn camera(&mut self, camera: &::gltf::Camera, transform: &Transform) {
+        match camera.projection() {
+            Projection::Perspective(perspective) => {
+                // Cameras look down their local -z axis with +y up.
+                self.scene.cameras.push(CameraSettings {
+                    lookfrom: transform.transform_point(&Point::default()),
+                    lookat: transform.transform_point(&Point::new(0.0, 0.0, -1.0)),
+                    vup: transform.transform_direction(&Vec3::new(0.0, 1.0, 0.0)),
+                    vfov: f64::from(perspective.yfov()).to_degrees(),
+                    aspect_ratio: perspective.aspect_ratio().map_or(16.0 / 9.0, f64::from),
+                    aperture: 0.0,
+                    focus_distance: 1.0,
+                    projection: camera::Projection::Perspective,
+                    time0: 0.0,
+                    time1: 0.0,
+                })
+            }
+            Projection::Orthographic(orthographic) => {
+                let (xmag, ymag) = (
+                    f64::from(orthographic.xmag()),
+                    f64::from(orthographic.ymag()),
+                );
+                self.scene.cameras.push(CameraSettings {
+                    lookfrom: transform.transform_point(&Point::default()),
+                    lookat: transform.transform_point(&Point::new(0.0, 0.0, -1.0)),
+                    vup: transform.transform_direction(&Vec3::new(0.0, 1.0, 0.0)),
+                    vfov: 0.0,
+                    aspect_ratio: xmag / ymag,
+                    aperture: 0.0,
+                    focus_distance: 1.0,
+                    // The magnifications are half the view's extent.
+                    projection: camera::Projection::Orthographic { height: 2.0 * ymag },
+                    time0: 0.0,
+                    time1: 0.0,
+                })
+            }
+        }
+    }
+    fn light(&mut self, light: &::gltf::khr_lights_punctual::Light, transform: &Transform) {
+        let power = vector(light.color()) * f64::from(light.intensity()) / LUMENS_PER_WATT;
+        let position = transform.transform_point(&Point::default());
+        let direction = transform
+            .transform_direction(&Vec3::new(0.0, 0.0, -1.0))
+            .unit();
+        self.scene.lights.push(match light.kind() {
+            Kind::Directional => Light::Distant {
+                direction: -direction,
+                irradiance: power,
+            },
+            Kind::Point => Light::Point {
+                position,
+                intensity: power,
+            },
+            Kind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => Light::Spot {
+                position,
+                direction,
+                intensity: power,
+                inner_angle: f64::from(inner_cone_angle),
+                outer_angle: f64::from(outer_cone_angle),
+            },
+        });
+    }
+}
+
+fn vector(v: [f32; 3]) -> Vec3 {
+    Vec3::new(f64::from(v[0]), f64::from(v[1]), f64::from(v[2]))
+}
+
+#[derive(Clone)]
+struct Product(Rc<dyn Texture>, Rc<dyn Texture>);
+
+impl Texture for Product {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.0.value(u, v, p) * self.1.value(u, v, p)
+    }
+    fn value_at(&self, rec: &HitRecord) -> Color {
+        self.0.value_at(rec) * self.1.value_at(rec)
+    }
+}
+
+// One channel of a texture, scaled and repeated in all three channels.
+#[derive(Clone)]
+struct Channel {
+    texture: Rc<dyn Texture>,
+    channel: usize,
+    factor: f64,
+}
+
+impl Texture for Channel {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        let x = self.factor * self.texture.value(u, v, p)[self.channel];
+        Color::new(x, x, x)
+    }
+}
+
+#[test]
+fn test_read_glb() {
+    let json = r#"{
+        "asset": {"version": "2.0"},
+        "scene": 0,
+        "scenes": [{"nodes": [0, 1, 2]}],
+        "nodes": [
+            {"mesh": 0, "translation": [0, 0, -2]},
+            {"camera": 0, "translation": [0, 1, 5]},
+            {"extensions": {"KHR_lights_punctual": {"light": 0}}, "translation": [0, 4, 0]}
+        ],
+        "meshes": [{"primitives": [{"attributes": {"POSITION": 0}, "material": 0}]}],
+        "materials": [{"pbrMetallicRoughness": {"baseColorFactor": [1, 0, 0, 1], "metallicFactor": 0.5}}],
+        "cameras": [{"type": "perspective", "perspective": {"yfov": 0.5, "aspectRatio": 2.0, "znear": 0.1}}],
+        "extensionsUsed": ["KHR_lights_punctual"],
+        "extensions": {"KHR_lights_punctual": {"lights": [{"type": "point", "intensity": 683}]}},
+        "buffers": [{"byteLength": 36}],
+        "bufferViews": [{"buffer": 0, "byteLength": 36}],
+        "accessors": [{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                       "min": [0, 0, 0], "max": [1, 1, 0]}]
+    }"#;
+    let mut json = json.as_bytes().to_vec();
+    while !json.len().is_multiple_of(4) {
+        json.push(b' ');
+    }
+    let mut bin = Vec::new();
+    for x in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0].iter() {
+        bin.extend_from_slice(&x.to_le_bytes());
+    }
+    let mut glb = Vec::new();
+    glb.extend_from_slice(b"glTF");
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&((12 + 8 + json.len() + 8 + bin.len()) as u32).to_le_bytes());
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"JSON");
+    glb.extend_from_slice(&json);
+    glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"BIN\0");
+    glb.extend_from_slice(&bin);
+
+    let scene = Scene::read_gltf(&glb).unwrap();
+    assert!(scene.warnings.is_empty(), "{:?}", scene.warnings);
+    assert_eq!(scene.objects.len(), 1);
+    let mut bounds = crate::aabb::Aabb::default();
+    assert!(scene.objects[0].bounding_box(0.0, 0.0, &mut bounds));
+    assert!((bounds.max()[2] + 2.0).abs() < 1e-3);
+    // The material is single-sided, so the triangle cannot be seen from behind.
+    let ray = |z: f64, direction: f64| crate::ray::Ray {
+        origin: Point::new(0.2, 0.2, z),
+        direction: Vec3::new(0.0, 0.0, direction),
+        ..crate::ray::Ray::default()
+    };
+    let mut rec = HitRecord::default();
+    assert!(scene.objects[0].hit(&ray(5.0, -1.0), 0.001, f64::INFINITY, &mut rec));
+    assert!(!scene.objects[0].hit(&ray(-5.0, 1.0), 0.001, f64::INFINITY, &mut rec));
+    let camera = &scene.cameras[0];
+    assert!((camera.lookfrom - Point::new(0.0, 1.0, 5.0)).length() < 1e-12);
+    assert!((camera.lookat - Point::new(0.0, 1.0, 4.0)).length() < 1e-12);
+    assert!((camera.vfov - 0.5f64.to_degrees()).abs() < 1e-5);
+    assert!((camera.aspect_ratio - 2.0).abs() < 1e-12);
+    match &scene.lights[0] {
+        Light::Point {
+            position,
+            intensity,
+        } => {
+            assert!((*position - Point::new(0.0, 4.0, 0.0)).length() < 1e-12);
+            assert!((*intensity - Color::new(1.0, 1.0, 1.0)).length() < 1e-12);
+        }
+        light => panic!("unexpected light {:?}", light),
+    }
+}
@@ -0,0 +1,348 @@
+use crate::{
+    hittable::HitRecord,
+    material::Material,
+    onb::Onb,
+    ray::Ray,
+    vec3::{Color, Vec3},
+};
+use rand::{Rng, RngCore};
+use std::f64::consts::{LN_2, PI};
+
+// Scattering lobes traced explicitly: R, TT and TRT. Everything after is lumped into one.
+const P_MAX: usize = 3;
+
+fn exp(c: Color) -> Color {
+    Color::new(c[0].exp(), c[1].exp(), c[2].exp())
+}
+
+fn safe_sqrt(x: f64) -> f64 {
+    x.max(0.0).sqrt()
+}
+
+fn safe_asin(x: f64) -> f64 {
+    x.clamp(-1.0, 1.0).asin()
+}
+
+fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+    let sin_theta_t = safe_sqrt(1.0 - cos_theta_i * cos_theta_i) / eta;
+    if sin_theta_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_theta_t = safe_sqrt(1.0 - sin_theta_t * sin_theta_t);
+    let parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    0.5 * (parallel * parallel + perpendicular * perpendicular)
+}
+
+// Modified Bessel function of the first kind, by its series.
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 0.0;
+    let mut term = 1.0;
+    let x2 = x * x / 4.0;
+    for i in 1..=10 {
+        sum += term;
+        term *= x2 / (i * i) as f64;
+    }
+    sum
+}
+
+fn log_bessel_i0(x: f64) -> f64 {
+    if x > 12.0 {
+        x + 0.5 * (-(2.0 * PI).ln() + (1.0 / x).ln() + 1.0 / (8.0 * x))
+    } else {
+        bessel_i0(x).ln()
+    }
+}
+
+// Longitudinal scattering.
+fn mp(cos_theta_i: f64, cos_theta_o: f64, sin_theta_i: f64, sin_theta_o: f64, v: f64) -> f64 {
+    let a = cos_theta_i * cos_theta_o / v;
+    let b = sin_theta_i * sin_theta_o / v;
+    if v <= 0.1 {
+        (log_bessel_i0(a) - b - 1.0 / v + LN_2 + (1.0 / (2.0 * v)).ln()).exp()
+    } else {
+        (-b).exp() * bessel_i0(a) / ((1.0 / v).sinh() * 2.0 * v)
+    }
+}
+
+// Attenuation of each lobe from Fresnel reflection and absorption inside the fiber.
+fn ap(cos_theta_o: f64, eta: f64, h: f64, transmittance: Color) -> [Color; P_MAX + 1] {
+    let cos_gamma_o = safe_sqrt(1.0 - h * h);
+    let f = fresnel_dielectric(cos_theta_o * cos_gamma_o, eta);
+    let r = Color::new(f, f, f);
+    let tt = (1.0 - f) * (1.0 - f) * transmittance;
+    let trt = tt * transmittance * f;
+    let mut rest = trt * f * transmittance;
+    for a in 0..3 {
+        rest[a] /= 1.0 - f * transmittance[a];
+    }
+    [r, tt, trt, rest]
+}
+
+fn phi(p: usize, gamma_o: f64, gamma_t: f64) -> f64 {
+    let p = p as f64;
+    2.0 * p * gamma_t - 2.0 * gamma_o + p * PI
+}
+
+fn logistic(x: f64, s: f64) -> f64 {
+    let x = x.abs();
+    (-x / s).exp() / (s * (1.0 + (-x / s).exp()).powi(2))
+}
+
+fn logistic_cdf(x: f64, s: f64) -> f64 {
+    1.0 / (1.0 + (-x / s).exp())
+}
+
+fn trimmed_logistic(x: f64, s: f64, a: f64, b: f64) -> f64 {
+    logistic(x, s) / (logistic_cdf(b, s) - logistic_cdf(a, s))
+}
+
+fn sample_trimmed_logistic(u: f64, s: f64, a: f64, b: f64) -> f64 {
+    let k = logistic_cdf(b, s) - logistic_cdf(a, s);
+    let x = -s * (1.0 / (u * k + logistic_cdf(a, s)) - 1.0).ln();
+    x.clamp(a, b)
+}
+
+// Azimuthal scattering.
+fn np(phi_difference: f64, p: usize, s: f64, gamma_o: f64, gamma_t: f64) -> f64 {
+    let mut dphi = phi_difference - phi(p, gamma_o, gamma_t);
+    while dphi > PI {
+        dphi -= 2.0 * PI;
+    }
+    while dphi < -PI {
+        dphi += 2.0 * PI;
+    }
+    trimmed_logistic(dphi, s, -PI, PI)
+}
+
+// Hair fiber scattering after Chiang et al. 2016, "A Practical and Controllable Hair and Fur
+// Model for Production Path Tracing", meant for `Curve`s. `sigma_a` is the absorption inside
+// the fiber per unit of its diameter; `beta_m` and `beta_n` are the longitudinal and azimuthal
+// roughness in [0, 1].
+#[derive(Clone)]
+pub struct Hair {
+    sigma_a: Color,
+    eta: f64,
+    beta_m: f64,
+    beta_n: f64,
+    alpha: f64,
+}
+
+impl Hair {
+    pub fn new(sigma_a: Color, beta_m: f64, beta_n: f64) -> Self {
+        Self {
+            sigma_a,
+            eta: 1.55,
+            beta_m: beta_m.clamp(0.0, 1.0),
+            beta_n: beta_n.clamp(0.0, 1.0),
+            alpha: 2.0,
+        }
+    }
+    // Absorption from the concentrations of the two pigments in real hair: eumelanin makes it
+    // brown to black, pheomelanin red.
+    pub fn new_melanin(eumelanin: f64, pheomelanin: f64, beta_m: f64, beta_n: f64) -> Self {
+        let eumelanin_sigma_a = Color::new(0.419, 0.697, 1.37);
+        let pheomelanin_sigma_a = Color::new(0.187, 0.4, 1.05);
+        Self::new(
+            eumelanin * eumelanin_sigma_a + pheomelanin * pheomelanin_sigma_a,
+            beta_m,
+            beta_n,
+        )
+    }
+    // Absorption that gives roughly `color` as the overall reflectance of a mass of hair.
+    pub fn new_reflectance(color: Color, beta_m: f64, beta_n: f64) -> Self {
+        let b = beta_n.clamp(0.0, 1.0);
+        let d = 5.969 - 0.215 * b + 2.532 * b.powi(2) - 10.73 * b.powi(3)
+            + 5.574 * b.powi(4)
+            + 0.245 * b.powi(5);
+        let mut sigma_a = Color::default();
+        for a in 0..3 {
+            sigma_a[a] = (color[a].max(1e-4).ln() / d).powi(2);
+        }
+        Self::new(sigma_a, beta_m, beta_n)
+    }
+    pub fn with_index_of_refraction(mut self, eta: f64) -> Self {
+        self.eta = eta;
+        self
+    }
+    // Tilt of the cuticle scales in degrees, which shifts the lobes along the fiber.
+    pub fn with_scale_angle(mut self, alpha: f64) -> Self {
+        self.alpha = alpha;
+        self
+    }
+    fn variances(&self) -> [f64; P_MAX + 1] {
+        let b = self.beta_m;
+        let v0 = (0.726 * b + 0.812 * b * b + 3.7 * b.powi(20)).powi(2);
+        [v0, 0.25 * v0, 4.0 * v0, 4.0 * v0]
+    }
+    fn logistic_scale(&self) -> f64 {
+        let b = self.beta_n;
+        (PI / 8.0).sqrt() * (0.265 * b + 1.194 * b * b + 5.372 * b.powi(22))
+    }
+    // sin and cos of the longitudinal shift of lobe p: -2 alpha for R, alpha for TT, 4 alpha
+    // for TRT; the remainder is not shifted.
+    fn tilted(&self, p: usize, sin_theta_o: f64, cos_theta_o: f64) -> (f64, f64) {
+        let shift = match p {
+            0 => -2.0,
+            1 => 1.0,
+            2 => 4.0,
+            _ => 0.0,
+        } * self.alpha.to_radians();
+        let (sin_shift, cos_shift) = shift.sin_cos();
+        (
+            sin_theta_o * cos_shift + cos_theta_o * sin_shift,
+            (cos_theta_o * cos_shift - sin_theta_o * sin_shift).abs(),
+        )
+    }
+    // (Attenuation of each lobe, refracted azimuth angle) for a ray leaving at `wo`.
+    fn attenuation(&self, sin_theta_o: f64, cos_theta_o: f64, h: f64) -> ([Color; 4], f64) {
+        let sin_theta_t = sin_theta_o / self.eta;
+        let cos_theta_t = safe_sqrt(1.0 - sin_theta_t * sin_theta_t);
+        let etap = safe_sqrt(self.eta * self.eta - sin_theta_o * sin_theta_o) / cos_theta_o;
+        let sin_gamma_t = h / etap;
+        let cos_gamma_t = safe_sqrt(1.0 - sin_gamma_t * sin_gamma_t);
+        let transmittance = exp(-self.sigma_a * (2.0 * cos_gamma_t / cos_theta_t));
+        (
+            ap(cos_theta_o, self.eta, h, transmittance),
+            safe_asin(sin_gamma_t),
+        )
+    }
+    fn lobe_pdfs(ap: &[Color; P_MAX + 1]) -> [f64; P_MAX + 1] {
+        let total: f64 = ap.iter().map(|a| a.luminance()).sum();
+        let mut pdfs = [0.0; P_MAX + 1];
+        for (pdf, a) in pdfs.iter_mut().zip(ap.iter()) {
+            *pdf = if total > 0.0 {
+                a.luminance() / total
+            } else {
+                1.0 / (P_MAX + 1) as f64
+            };
+        }
+        pdfs
+    }
+}
+
+impl Material for Hair {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+        rng: &mut dyn RngCore,
+    ) -> bool {
+        // Local frame with x along the fiber and z along the normal.
+        let n = rec.normal;
+        let tangent = rec
+            .tangent
+            .map(|t| t - t.dot(&n) * n)
+            .filter(|t| !t.is_near_zero())
+            .unwrap_or_else(|| Onb::new_from_w(&n).u())
+            .unit();
+        let bitangent = n.cross(&tangent);
+        let wo = -r_in.direction.unit();
+        let wo = Vec3::new(wo.dot(&tangent), wo.dot(&bitangent), wo.dot(&n));
+        let h = (2.0 * rec.v - 1.0).clamp(-1.0, 1.0);
+        let gamma_o = safe_asin(h);
+
+        let sin_theta_o = wo[0].clamp(-1.0, 1.0);
+        let cos_theta_o = safe_sqrt(1.0 - sin_theta_o * sin_theta_o).max(1e-6);
+        let phi_o = wo[2].atan2(wo[1]);
+        let (ap, gamma_t) = self.attenuation(sin_theta_o, cos_theta_o, h);
+        let lobe_pdfs = Self::lobe_pdfs(&ap);
+        let v = self.variances();
+        let s = self.logistic_scale();
+
+        // Pick a lobe, then its longitudinal and azimuthal angles.
+        let mut u: f64 = rng.gen();
+        let mut p = 0;
+        while p < P_MAX && u >= lobe_pdfs[p] {
+            u -= lobe_pdfs[p];
+            p += 1;
+        }
+        let (sin_theta_op, cos_theta_op) = self.tilted(p, sin_theta_o, cos_theta_o);
+        let u1 = rng.gen::<f64>().max(1e-5);
+        let cos_theta = 1.0 + v[p] * (u1 + (1.0 - u1) * (-2.0 / v[p]).exp()).ln();
+        let sin_theta = safe_sqrt(1.0 - cos_theta * cos_theta);
+        let cos_phi = (2.0 * PI * rng.gen::<f64>()).cos();
+        let sin_theta_i =
+            (-cos_theta * sin_theta_op + sin_theta * cos_phi * cos_theta_op).clamp(-1.0, 1.0);
+        let cos_theta_i = safe_sqrt(1.0 - sin_theta_i * sin_theta_i);
+        let dphi = if p < P_MAX {
+            phi(p, gamma_o, gamma_t) + sample_trimmed_logistic(rng.gen(), s, -PI, PI)
+        } else {
+            2.0 * PI * rng.gen::<f64>()
+        };
+        let phi_i = phi_o + dphi;
+        let wi = Vec3::new(
+            sin_theta_i,
+            cos_theta_i * phi_i.cos(),
+            cos_theta_i * phi_i.sin(),
+        );
+
+        // The BSDF times the cosine, and the pdf of sampling wi, summed over all lobes.
+        let mut f = Color::default();
+        let mut pdf = 0.0;
+        for (lobe, a) in ap.iter().enumerate() {
+            let (m, n) = if lobe < P_MAX {
+                let (sin_theta_op, cos_theta_op) = self.tilted(lobe, sin_theta_o, cos_theta_o);
+                (
+                    mp(
+                        cos_theta_i,
+                        cos_theta_op,
+                        sin_theta_i,
+                        sin_theta_op,
+                        v[lobe],
+                    ),
+                    np(dphi, lobe, s, gamma_o, gamma_t),
+                )
+            } else {
+                (
+                    mp(cos_theta_i, cos_theta_o, sin_theta_i, sin_theta_o, v[lobe]),
+                    1.0 / (2.0 * PI),
+                )
+            };
+            f += m * n * *a;
+            pdf += m * n * lobe_pdfs[lobe];
+        }
+        if pdf <= 0.0 || !pdf.is_finite() {
+            return false;
+        }
+        *scattered = Ray {
+            origin: rec.point,
+            direction: wi[0] * tangent + wi[1] * bitangent + wi[2] * n,
+            wavelength: r_in.wavelength,
+            time: r_in.time,
+        };
+        *attenuation = f / pdf;
+        true
+    }
+}
+
+#[test]
+fn test_hair_white_furnace() {
+    // Without absorption a fiber loses no energy: on average the weights are one.
+    let hair = Hair::new(Color::default(), 0.3, 0.3);
+    let mut rng = rand::thread_rng();
+    let mut sum = Color::default();
+    let n = 100000;
+    for _ in 0..n {
+        let rec = HitRecord {
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            v: rng.gen(),
+            tangent: Some(Vec3::new(1.0, 0.0, 0.0)),
+            ..HitRecord::default()
+        };
+        let r_in = Ray {
+            direction: -Vec3::new_random_in_hemisphere(&mut rng, &rec.normal),
+            ..Ray::default()
+        };
+        let mut attenuation = Color::default();
+        let mut scattered = Ray::default();
+        if hair.scatter(&r_in, &rec, &mut attenuation, &mut scattered, &mut rng) {
+            sum += attenuation;
+        }
+    }
+    let mean = sum / n as f64;
+    assert!((mean[1] - 1.0).abs() < 0.05, "{:?}", mean);
+}
@@ -0,0 +1,425 @@
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    ray::Ray,
+    vec3::{Point, Vec3},
+};
+use std::{
+    convert::TryInto,
+    fs::File,
+    io::{self, BufReader, Read},
+    path::Path,
+    rc::Rc,
+};
+
+// A terrain of `width` by `depth` height samples on a regular grid, with two triangles per
+// cell. Rays walk the cells they cross in order and skip cells whose height range they pass
+// above or below, so even large grids need no per-triangle objects.
+#[derive(Clone)]
+pub struct Heightfield {
+    width: usize,
+    depth: usize,
+    // World-space heights, row by row along +z.
+    heights: Rc<Vec<f64>>,
+    // Lowest and highest height of each cell.
+    cell_ranges: Rc<Vec<(f64, f64)>>,
+    // Normals at the samples, from central differences.
+    normals: Rc<Vec<Vec3>>,
+    corner: Point,
+    spacing: (f64, f64),
+    bounds: Aabb,
+    material: Rc<dyn Material>,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn intersect_triangle(r: &Ray, p0: Point, p1: Point, p2: Point) -> Option<f64> {
+    let edge1 = p1 - p0;
+    let edge2 = p2 - p0;
+    let pvec = r.direction.cross(&edge2);
+    let det = edge1.dot(&pvec);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let tvec = r.origin - p0;
+    let b1 = tvec.dot(&pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let qvec = tvec.cross(&edge1);
+    let b2 = r.direction.dot(&qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+    Some(edge2.dot(&qvec) * inv_det)
+}
+
+// Reads a grayscale PGM (P2 or P5, scaled to [0, 1]) or a PFM float raster (Pf, or PF
+// averaged over its channels), top row first.
+fn read_raster(data: &[u8]) -> io::Result<(usize, usize, Vec<f64>)> {
+    let mut pos = 0;
+    let mut token = || -> io::Result<String> {
+        loop {
+            match data.get(pos) {
+                Some(b'#') => {
+                    while data.get(pos).is_some_and(|&c| c != b'\n') {
+                        pos += 1;
+                    }
+                }
+                Some(c) if c.is_ascii_whitespace() => pos += 1,
+                Some(_) => break,
+                None => return Err(invalid("truncated raster header")),
+            }
+        }
+        let start = pos;
+        while data.get(pos).is_some_and(|c| !c.is_ascii_whitespace()) {
+            pos += 1;
+        }
+        Ok(String::from_utf8_lossy(&data[start..pos]).into_owned())
+    };
+    let magic = token()?;
+    let number = |s: String| {
+        s.parse::<f64>()
+            .map_err(|_| invalid("malformed raster header"))
+    };
+    let dimension = |s: String| {
+        s.parse::<usize>()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| invalid("bad raster dimensions"))
+    };
+    let width = dimension(token()?)?;
+    let height = dimension(token()?)?;
+    let scale = number(token()?)?;
+    let count = width
+        .checked_mul(height)
+        .ok_or_else(|| invalid("bad raster dimensions"))?;
+    let body = |pos: usize, size: usize| {
+        // Exactly one whitespace character separates the header from binary data.
+        count
+            .checked_mul(size)
+            .and_then(|length| data.get(pos + 1..).and_then(|rest| rest.get(..length)))
+            .ok_or_else(|| invalid("truncated raster data"))
+    };
+
+    let values = match magic.as_str() {
+        "P2" => {
+            let text = String::from_utf8_lossy(&data[pos..]);
+            let values: Vec<f64> = text
+                .split_whitespace()
+                .take(count)
+                .map(|s| s.parse::<f64>().map(|x| x / scale))
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid("malformed raster data"))?;
+            if values.len() != count {
+                return Err(invalid("truncated raster data"));
+            }
+            values
+        }
+        "P5" if scale < 256.0 => body(pos, 1)?
+            .iter()
+            .map(|&x| f64::from(x) / scale)
+            .collect(),
+        "P5" => body(pos, 2)?
+            .chunks_exact(2)
+            .map(|x| f64::from(u16::from_be_bytes([x[0], x[1]])) / scale)
+            .collect(),
+        "Pf" | "PF" => {
+            let channels = if magic == "PF" { 3 } else { 1 };
+            let floats: Vec<f64> = body(pos, 4 * channels)?
+                .chunks_exact(4)
+                .map(|x| {
+                    let bytes = x.try_into().unwrap();
+                    f64::from(if scale < 0.0 {
+                        f32::from_le_bytes(bytes)
+                    } else {
+                        f32::from_be_bytes(bytes)
+                    })
+                })
+                .collect();
+            // PFM stores the bottom row first.
+            let mut values = Vec::with_capacity(count);
+            for row in floats.chunks_exact(width * channels).rev() {
+                for pixel in row.chunks_exact(channels) {
+                    values.push(pixel.iter().sum::<f64>() / channels as f64);
+                }
+            }
+            values
+        }
+        _ => return Err(invalid("not a PGM or PFM raster")),
+    };
+    Ok((width, height, values))
+}
+
+impl Heightfield {
+    // `heights` has `width` samples along x per row and `depth` rows along z, and is scaled
+    // by `size.y`; the grid spans `size.x` by `size.z` from `corner`.
+    pub fn new(
+        width: usize,
+        depth: usize,
+        heights: Vec<f64>,
+        corner: Point,
+        size: Vec3,
+        material: Rc<dyn Material>,
+    ) -> Self {
+        assert!(width >= 2 && depth >= 2, "a heightfield needs 2x2 samples");
+        assert_eq!(
+            heights.len(),
+            width * depth,
+            "sample count must be width * depth"
+        );
+        let heights: Vec<f64> = heights.iter().map(|h| corner[1] + h * size[1]).collect();
+        let spacing = (size[0] / (width - 1) as f64, size[2] / (depth - 1) as f64);
+        let h = |i: usize, j: usize| heights[j * width + i];
+
+        let mut cell_ranges = Vec::with_capacity((width - 1) * (depth - 1));
+        for j in 0..depth - 1 {
+            for i in 0..width - 1 {
+                let corners = [h(i, j), h(i + 1, j), h(i, j + 1), h(i + 1, j + 1)];
+                cell_ranges.push((
+                    corners.iter().copied().fold(f64::INFINITY, f64::min),
+                    corners.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                ));
+            }
+        }
+        let mut normals = Vec::with_capacity(heights.len());
+        for j in 0..depth {
+            for i in 0..width {
+                let (i0, i1) = (i.saturating_sub(1), (i + 1).min(width - 1));
+                let (j0, j1) = (j.saturating_sub(1), (j + 1).min(depth - 1));
+                let dhdx = (h(i1, j) - h(i0, j)) / ((i1 - i0) as f64 * spacing.0);
+                let dhdz = (h(i, j1) - h(i, j0)) / ((j1 - j0) as f64 * spacing.1);
+                normals.push(Vec3::new(-dhdx, 1.0, -dhdz).unit());
+            }
+        }
+
+        let low = heights.iter().copied().fold(f64::INFINITY, f64::min);
+        let high = heights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let bounds = Aabb::new(
+            Point::new(corner[0], low, corner[2]),
+            Point::new(corner[0] + size[0], high, corner[2] + size[2]),
+        )
+        .padded(1e-4);
+        Self {
+            width,
+            depth,
+            heights: Rc::new(heights),
+            cell_ranges: Rc::new(cell_ranges),
+            normals: Rc::new(normals),
+            corner,
+            spacing,
+            bounds,
+            material,
+        }
+    }
+    pub fn load<P>(
+        path: P,
+        corner: Point,
+        size: Vec3,
+        material: Rc<dyn Material>,
+    ) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::read(
+            &mut BufReader::new(File::open(path)?),
+            corner,
+            size,
+            material,
+        )
+    }
+    // Reads a PGM or PFM raster; the top row of the image is at `corner.z`.
+    pub fn read(
+        reader: &mut dyn Read,
+        corner: Point,
+        size: Vec3,
+        material: Rc<dyn Material>,
+    ) -> io::Result<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let (width, depth, heights) = read_raster(&data)?;
+        if width < 2 || depth < 2 {
+            return Err(invalid("a heightfield needs 2x2 samples"));
+        }
+        Ok(Self::new(width, depth, heights, corner, size, material))
+    }
+    fn sample(&self, i: usize, j: usize) -> Point {
+        Point::new(
+            self.corner[0] + i as f64 * self.spacing.0,
+            self.heights[j * self.width + i],
+            self.corner[2] + j as f64 * self.spacing.1,
+        )
+    }
+    // Nearest hit on the two triangles of cell (i, j), with the triangle's upward normal.
+    fn hit_cell(&self, r: &Ray, i: usize, j: usize, t_min: f64, t_max: f64) -> Option<(f64, Vec3)> {
+        let p00 = self.sample(i, j);
+        let p10 = self.sample(i + 1, j);
+        let p01 = self.sample(i, j + 1);
+        let p11 = self.sample(i + 1, j + 1);
+        [(p00, p11, p10), (p00, p01, p11)]
+            .iter()
+            .filter_map(|&(a, b, c)| {
+                intersect_triangle(r, a, b, c)
+                    .filter(|t| (t_min..=t_max).contains(t))
+                    .map(|t| (t, (b - a).cross(&(c - a))))
+            })
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let (t_enter, t_exit) = match self.bounds.hit_interval(r, t_min, t_max) {
+            Some(interval) => interval,
+            None => return false,
+        };
+        let cells = (self.width - 1, self.depth - 1);
+        let grid = |p: &Point| {
+            (
+                (p[0] - self.corner[0]) / self.spacing.0,
+                (p[2] - self.corner[2]) / self.spacing.1,
+            )
+        };
+        let start = grid(&r.at(t_enter));
+        let mut i = (start.0.floor().max(0.0) as usize).min(cells.0 - 1);
+        let mut j = (start.1.floor().max(0.0) as usize).min(cells.1 - 1);
+
+        // Amanatides and Woo's traversal over the cells in x and z.
+        let axis_setup = |d: f64, o: f64, corner: f64, spacing: f64, cell: usize| {
+            if d > 0.0 {
+                (
+                    1,
+                    (corner + (cell + 1) as f64 * spacing - o) / d,
+                    spacing / d,
+                )
+            } else if d < 0.0 {
+                (-1, (corner + cell as f64 * spacing - o) / d, -spacing / d)
+            } else {
+                (0, f64::INFINITY, f64::INFINITY)
+            }
+        };
+        let (step_x, mut next_x, delta_x) = axis_setup(
+            r.direction[0],
+            r.origin[0],
+            self.corner[0],
+            self.spacing.0,
+            i,
+        );
+        let (step_z, mut next_z, delta_z) = axis_setup(
+            r.direction[2],
+            r.origin[2],
+            self.corner[2],
+            self.spacing.1,
+            j,
+        );
+
+        let mut t0 = t_enter;
+        loop {
+            let t1 = next_x.min(next_z).min(t_exit);
+            let (low, high) = self.cell_ranges[j * cells.0 + i];
+            let (y0, y1) = (r.at(t0)[1], r.at(t1)[1]);
+            if y0.min(y1) <= high && y0.max(y1) >= low {
+                if let Some((t, geometric)) = self.hit_cell(r, i, j, t_min, t_max) {
+                    let point = r.at(t);
+                    let (gx, gz) = grid(&point);
+                    let (fx, fz) = (
+                        (gx - i as f64).clamp(0.0, 1.0),
+                        (gz - j as f64).clamp(0.0, 1.0),
+                    );
+                    let n = |i: usize, j: usize| self.normals[j * self.width + i];
+                    let shading = (1.0 - fz) * ((1.0 - fx) * n(i, j) + fx * n(i + 1, j))
+                        + fz * ((1.0 - fx) * n(i, j + 1) + fx * n(i + 1, j + 1));
+                    rec.t = t;
+                    rec.point = point;
+                    rec.front_face = r.direction.dot(&geometric) < 0.0;
+                    let shading = shading.unit();
+                    rec.normal = if rec.front_face { shading } else { -shading };
+                    rec.u = gx / cells.0 as f64;
+                    rec.v = 1.0 - gz / cells.1 as f64;
+                    rec.vertex_color = None;
+                    rec.material = self.material.clone();
+                    return true;
+                }
+            }
+            if t1 >= t_exit {
+                return false;
+            }
+            t0 = t1;
+            if next_x < next_z {
+                if (step_x < 0 && i == 0) || (step_x > 0 && i + 1 == cells.0) {
+                    return false;
+                }
+                i = (i as isize + step_x) as usize;
+                next_x += delta_x;
+            } else {
+                if (step_z < 0 && j == 0) || (step_z > 0 && j + 1 == cells.1) {
+                    return false;
+                }
+                j = (j as isize + step_z) as usize;
+                next_z += delta_z;
+            }
+        }
+    }
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        *output_box = self.bounds;
+        true
+    }
+}
+
+#[test]
+fn test_heightfield() {
+    use crate::{material::Lambertian, vec3::Color};
+
+    // A 3x3 grid over [0, 2] x [0, 2] with a peak of height 1 in the middle.
+    let pgm = b"P2\n# peak\n3 3\n255\n0 0 0\n0 255 0\n0 0 0\n";
+    let field = Heightfield::read(
+        &mut &pgm[..],
+        Point::default(),
+        Vec3::new(2.0, 1.0, 2.0),
+        Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )
+    .unwrap();
+    let mut rec = HitRecord::default();
+    let down = |x: f64, z: f64| Ray {
+        origin: Point::new(x, 5.0, z),
+        direction: Vec3::new(0.0, -1.0, 0.0),
+        ..Default::default()
+    };
+    assert!(field.hit(&down(1.0, 1.0), 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.point[1] - 1.0).abs() < 1e-9);
+    assert!((rec.normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+    assert!(field.hit(&down(0.5, 1.0), 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.point[1] - 0.5).abs() < 1e-9);
+    assert!(rec.normal[0] < 0.0 && rec.front_face);
+    assert!(!field.hit(&down(2.5, 1.0), 0.001, f64::INFINITY, &mut rec));
+
+    // A grazing ray crosses several cells before it meets the slope.
+    let grazing = Ray {
+        origin: Point::new(-1.0, 0.75, 1.0),
+        direction: Vec3::new(1.0, 0.0, 0.0),
+        ..Default::default()
+    };
+    assert!(field.hit(&grazing, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.point[0] - 0.75).abs() < 1e-9);
+    let above = Ray {
+        origin: Point::new(-1.0, 1.5, 0.5),
+        ..grazing
+    };
+    assert!(!field.hit(&above, 0.001, f64::INFINITY, &mut rec));
+
+    // Hostile headers are rejected rather than panicking or overflowing.
+    for header in [
+        &b"Pf\n0 2\n-1\n"[..],
+        &b"Pf\n-3 2\n-1\n"[..],
+        &b"P5\n18446744073709551615 3\n255\n"[..],
+        &b"PF\n4294967296 4294967296\n-1\n"[..],
+    ]
+    .iter()
+    {
+        assert!(read_raster(header).is_err());
+    }
+}
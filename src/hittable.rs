@@ -1,4 +1,5 @@
 use crate::{
+    aabb::Aabb,
     material::{Lambertian, Material},
     ray::Ray,
     vec3::{Color, Point, Vec3},
@@ -11,10 +12,22 @@ pub struct HitRecord {
     pub normal: Vec3,
     pub material: Rc<dyn Material>,
     pub t: f64,
+    pub u: f64,
+    pub v: f64,
     pub front_face: bool,
+    // Interpolated vertex color for meshes that carry one.
+    pub vertex_color: Option<Color>,
+    // Direction along the fiber for curves, which hair shading needs.
+    pub tangent: Option<Vec3>,
 }
 
 impl HitRecord {
+    // Forgets attributes that only some surfaces set, so a record reused for the next object
+    // cannot carry them over from the previous hit.
+    pub fn clear_attributes(&mut self) {
+        self.vertex_color = None;
+        self.tangent = None;
+    }
     pub fn set_face_normal(&mut self, r: &Ray, outward_normal: &Vec3) {
         self.front_face = r.direction.dot(outward_normal) < 0.0;
         self.normal = if self.front_face {
@@ -32,11 +45,56 @@ impl Default for HitRecord {
             normal: Vec3::default(),
             material: Rc::new(Lambertian::new(Color::default())),
             t: 0.0,
+            u: 0.0,
+            v: 0.0,
             front_face: false,
+            vertex_color: None,
+            tangent: None,
         }
     }
 }
 
 pub trait Hittable {
     fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool;
+    // Box enclosing the object over the whole time interval; false for unbounded objects.
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool;
+    // Appends every surface crossing in [t_min, t_max] to `hits`, in increasing t.
+    fn hit_all(&self, r: &Ray, t_min: f64, t_max: f64, hits: &mut Vec<HitRecord>) {
+        let mut rec = HitRecord::default();
+        let mut t = t_min;
+        while self.hit(r, t, t_max, &mut rec) {
+            t = rec.t + 1e-7 * rec.t.abs().max(1.0);
+            hits.push(rec.clone());
+        }
+    }
+}
+
+impl<T> Hittable for Box<T>
+where
+    T: Hittable + ?Sized,
+{
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        (**self).hit(r, t_min, t_max, rec)
+    }
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        (**self).bounding_box(time0, time1, output_box)
+    }
+    fn hit_all(&self, r: &Ray, t_min: f64, t_max: f64, hits: &mut Vec<HitRecord>) {
+        (**self).hit_all(r, t_min, t_max, hits)
+    }
+}
+
+impl<T> Hittable for Rc<T>
+where
+    T: Hittable + ?Sized,
+{
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        (**self).hit(r, t_min, t_max, rec)
+    }
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        (**self).bounding_box(time0, time1, output_box)
+    }
+    fn hit_all(&self, r: &Ray, t_min: f64, t_max: f64, hits: &mut Vec<HitRecord>) {
+        (**self).hit_all(r, t_min, t_max, hits)
+    }
 }
@@ -1,8 +1,11 @@
 use crate::{
+    aabb::{surrounding_box, Aabb},
     hittable::{HitRecord, Hittable},
-    material::{Dielectric, Lambertian, Material, Metal},
+    material::{Dielectric, DiffuseLight, Lambertian, Material, Metal},
+    quad::{Cuboid, Quad},
     ray::Ray,
     sphere::Sphere,
+    texture::SolidColor,
     vec3::{Color, Point},
 };
 use rand::prelude::*;
@@ -17,6 +20,9 @@ impl<T> HittableList<T>
 where
     T: Hittable,
 {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
     #[allow(dead_code)]
     pub fn clear(&mut self) {
         self.0.clear();
@@ -37,6 +43,7 @@ where
         let mut closest_so_far = t_max;
 
         for item in self.0.iter() {
+            temp.clear_attributes();
             if item.hit(r, t_min, closest_so_far, &mut temp) {
                 hit_anything = true;
                 closest_so_far = temp.t;
@@ -45,6 +52,25 @@ where
         }
         hit_anything
     }
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        if self.0.is_empty() {
+            return false;
+        }
+        let mut temp_box = Aabb::default();
+        let mut first_box = true;
+        for item in self.0.iter() {
+            if !item.bounding_box(time0, time1, &mut temp_box) {
+                return false;
+            }
+            *output_box = if first_box {
+                temp_box
+            } else {
+                surrounding_box(output_box, &temp_box)
+            };
+            first_box = false;
+        }
+        true
+    }
 }
 
 pub fn random_scene(rng: &mut dyn rand::RngCore) -> HittableList<Sphere> {
@@ -98,3 +124,113 @@ pub fn random_scene(rng: &mut dyn rand::RngCore) -> HittableList<Sphere> {
     ));
     HittableList::<Sphere>(v)
 }
+
+pub fn cornell_box() -> HittableList<Box<dyn Hittable>> {
+    let red: Rc<dyn Material> = Rc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
+    let white: Rc<dyn Material> = Rc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let green: Rc<dyn Material> = Rc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
+    let light: Rc<dyn Material> = Rc::new(DiffuseLight::new(Rc::new(SolidColor::new(Color::new(
+        15.0, 15.0, 15.0,
+    )))));
+
+    let mut world: HittableList<Box<dyn Hittable>> = HittableList::new();
+    world.push(Box::new(Quad::new_yz(0.0, 555.0, 0.0, 555.0, 555.0, green)));
+    world.push(Box::new(Quad::new_yz(0.0, 555.0, 0.0, 555.0, 0.0, red)));
+    world.push(Box::new(Quad::new_xz(
+        213.0, 343.0, 227.0, 332.0, 554.0, light,
+    )));
+    world.push(Box::new(Quad::new_xz(
+        0.0,
+        555.0,
+        0.0,
+        555.0,
+        0.0,
+        white.clone(),
+    )));
+    world.push(Box::new(Quad::new_xz(
+        0.0,
+        555.0,
+        0.0,
+        555.0,
+        555.0,
+        white.clone(),
+    )));
+    world.push(Box::new(Quad::new_xy(
+        0.0,
+        555.0,
+        0.0,
+        555.0,
+        555.0,
+        white.clone(),
+    )));
+    world.push(Box::new(Cuboid::new(
+        Point::new(130.0, 0.0, 65.0),
+        Point::new(295.0, 165.0, 230.0),
+        white.clone(),
+    )));
+    world.push(Box::new(Cuboid::new(
+        Point::new(265.0, 0.0, 295.0),
+        Point::new(430.0, 330.0, 460.0),
+        white,
+    )));
+    world
+}
+
+#[test]
+fn test_hit_attributes_do_not_leak() {
+    use crate::{
+        bvh::Bvh,
+        curve::{Curve, CurveType},
+        hittable::Hittable,
+        mesh::{Mesh, TriangleMesh},
+        vec3::Vec3,
+    };
+
+    // A colored triangle and a curve behind a plain sphere, tested first so their hits fill the
+    // record.
+    let material: Rc<dyn Material> = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let triangle = TriangleMesh {
+        positions: vec![
+            Point::new(-5.0, -5.0, -5.0),
+            Point::new(5.0, -5.0, -5.0),
+            Point::new(0.0, 5.0, -5.0),
+        ],
+        colors: vec![Color::new(1.0, 0.0, 0.0); 3],
+        indices: vec![[0, 1, 2]],
+        ..TriangleMesh::default()
+    };
+    let objects = || -> Vec<Box<dyn Hittable>> {
+        vec![
+            Box::new(Mesh::new(triangle.clone(), material.clone())),
+            Box::new(Curve::new(
+                [
+                    Point::new(-2.0, 0.0, -3.0),
+                    Point::new(-1.0, 0.0, -3.0),
+                    Point::new(1.0, 0.0, -3.0),
+                    Point::new(2.0, 0.0, -3.0),
+                ],
+                0.5,
+                0.5,
+                CurveType::Flat,
+                material.clone(),
+            )),
+            Box::new(Sphere::new(Point::default(), 1.0, material.clone())),
+        ]
+    };
+    let r = Ray {
+        origin: Point::new(0.0, 0.0, 5.0),
+        direction: Vec3::new(0.0, 0.0, -1.0),
+        ..Ray::default()
+    };
+    let mut list = HittableList::new();
+    for object in objects() {
+        list.push(object);
+    }
+    let bvh = Bvh::new(objects(), 0.0, 0.0);
+    for world in [&list as &dyn Hittable, &bvh].iter() {
+        let mut rec = HitRecord::default();
+        assert!(world.hit(&r, 0.001, f64::INFINITY, &mut rec));
+        assert!((rec.t - 4.0).abs() < 1e-9);
+        assert!(rec.vertex_color.is_none() && rec.tangent.is_none());
+    }
+}
@@ -1,50 +1,198 @@
 use crate::{
-    camera::Camera,
-    hittable_list::random_scene,
+    atmosphere::{HeightFog, Sun},
+    camera::{Camera, CameraSettings, FisheyeMapping, Projection, StereoLayout, StereoRig},
+    hittable::Hittable,
+    hittable_list::{cornell_box, random_scene},
+    scene::Scene,
+    spectrum::{radiance_to_xyz, sample_wavelength_stratified, xyz_to_rgb},
     vec3::{Color, Point, Vec3},
 };
 use rand::prelude::*;
+use std::path::Path;
+
+// Punctual lights in imported scenes become emissive spheres this big, in scene units.
+const LIGHT_RADIUS: f64 = 0.05;
+
+// Imports a scene file, chosen by its extension.
+fn load_scene(path: &str) -> std::io::Result<Scene> {
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("gltf") | Some("glb") => Scene::load_gltf(path),
+        Some("pbrt") => Scene::load_pbrt(path),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "unknown scene or unsupported file type",
+        )),
+    }
+}
+
+// Image coordinates of a random point inside pixel (i, j), with rows counted from the bottom.
+// Pixel edges fall on multiples of 1 / width, so a stereo image splits between two pixels.
+fn pixel_sample(
+    i: usize,
+    j: usize,
+    width: usize,
+    height: usize,
+    rng: &mut dyn RngCore,
+) -> (f64, f64) {
+    (
+        (i as f64 + rng.gen::<f64>()) / width as f64,
+        (j as f64 + rng.gen::<f64>()) / height as f64,
+    )
+}
 
 pub fn process() {
     let mut rng = rand::thread_rng();
+    let spectral = std::env::args().any(|arg| arg == "--spectral");
+    let fog = if std::env::args().any(|arg| arg == "--fog") {
+        if spectral {
+            eprintln!("--fog does not support --spectral");
+            return;
+        }
+        // Ground fog thinning out above the spheres, lit by a low sun behind the camera.
+        let sun = Sun::new(Vec3::new(1.0, 0.6, 0.3), Color::new(3.0, 2.8, 2.5), 0.27);
+        Some(HeightFog::new(0.05, 0.0, 0.8, 0.5).with_sun(sun))
+    } else {
+        None
+    };
+    let option =
+        |name: &str| std::env::args().find_map(|arg| arg.strip_prefix(name).map(str::to_string));
+    // Imported scenes bring their own projection unless one is asked for.
+    let projection = match option("--camera=").as_deref() {
+        None => None,
+        Some("perspective") => Some(Projection::Perspective),
+        Some("orthographic") => Some(Projection::Orthographic { height: 4.0 }),
+        Some("fisheye") => Some(Projection::Fisheye {
+            mapping: FisheyeMapping::Equidistant,
+            fov: 180.0,
+        }),
+        Some("equisolid") => Some(Projection::Fisheye {
+            mapping: FisheyeMapping::Equisolid,
+            fov: 180.0,
+        }),
+        Some("equirectangular") => Some(Projection::Equirectangular),
+        Some(other) => {
+            eprintln!("unknown camera \"{}\"", other);
+            return;
+        }
+    };
+    let stereo = match option("--stereo=").as_deref() {
+        None => None,
+        Some("parallel") => Some(StereoRig::Parallel),
+        Some("toe-in") => Some(StereoRig::ToeIn),
+        Some(other) => {
+            eprintln!("unknown stereo rig \"{}\"", other);
+            return;
+        }
+    };
+    let layout = match option("--layout=").as_deref() {
+        None | Some("side-by-side") => StereoLayout::SideBySide,
+        Some("top-bottom") => StereoLayout::TopBottom,
+        Some(other) => {
+            eprintln!("unknown stereo layout \"{}\"", other);
+            return;
+        }
+    };
+    let interocular = match option("--interocular=").map(|x| x.parse::<f64>()) {
+        None => 0.065,
+        Some(Ok(distance)) => distance,
+        Some(Err(_)) => {
+            eprintln!("malformed interocular distance");
+            return;
+        }
+    };
 
-    // Image
-    struct AspectRatio {
-        width: usize,
-        height: usize,
-    }
-    impl AspectRatio {
-        fn ratio(&self) -> f64 {
-            self.width as f64 / self.height as f64
+    // World, camera and, for imported scenes, the image size they ask for
+    let (world, mut settings, mut resolution): (
+        Box<dyn Hittable>,
+        CameraSettings,
+        Option<(usize, usize)>,
+    ) = match option("--scene=").as_deref() {
+        None | Some("spheres") => (
+            Box::new(random_scene(&mut rng)),
+            CameraSettings {
+                lookfrom: Point::new(13.0, 2.0, 3.0),
+                lookat: Point::new(0.0, 0.0, 0.0),
+                vup: Vec3::new(0.0, 1.0, 0.0),
+                vfov: 20.0,
+                aspect_ratio: 3.0 / 2.0,
+                aperture: 0.1,
+                focus_distance: 10.0,
+                projection: Projection::Perspective,
+                time0: 0.0,
+                time1: 1.0,
+            },
+            None,
+        ),
+        Some("cornell") => (
+            Box::new(cornell_box()),
+            CameraSettings {
+                lookfrom: Point::new(278.0, 278.0, -800.0),
+                lookat: Point::new(278.0, 278.0, 0.0),
+                vup: Vec3::new(0.0, 1.0, 0.0),
+                vfov: 40.0,
+                aspect_ratio: 1.0,
+                aperture: 0.0,
+                focus_distance: 10.0,
+                projection: Projection::Perspective,
+                time0: 0.0,
+                time1: 1.0,
+            },
+            None,
+        ),
+        Some(path) => {
+            let scene = match load_scene(path) {
+                Ok(scene) => scene,
+                Err(error) => {
+                    eprintln!("cannot load \"{}\": {}", path, error);
+                    return;
+                }
+            };
+            for warning in scene.warnings.iter() {
+                eprintln!("{}: {}", path, warning);
+            }
+            let settings = match scene.cameras.first() {
+                Some(settings) => settings.clone(),
+                None => {
+                    eprintln!("\"{}\" has no camera", path);
+                    return;
+                }
+            };
+            let resolution = scene.resolution;
+            (
+                Box::new(scene.into_world(LIGHT_RADIUS)),
+                settings,
+                resolution,
+            )
+        }
+    };
+    if let Some(projection) = projection {
+        settings.projection = projection;
+        if projection == Projection::Equirectangular {
+            settings.aspect_ratio = 2.0;
+            resolution = None;
         }
     }
-    let aspect_ratio = AspectRatio {
-        width: 3,
-        height: 2,
+
+    // Image
+    let (eye_width, eye_height) = resolution.unwrap_or_else(|| {
+        let width = 1200;
+        (
+            width,
+            (width as f64 / settings.aspect_ratio).round() as usize,
+        )
+    });
+    let (image_width, image_height) = match stereo {
+        Some(_) => layout.image_size(eye_width, eye_height),
+        None => (eye_width, eye_height),
     };
-    let image_width = 1200;
-    let image_height = image_width * aspect_ratio.height / aspect_ratio.width;
     let samples_per_pixel = 500;
     let max_depth = 50;
 
-    // World
-    let world = random_scene(&mut rng);
-
     // Camera
-    let lookfrom = Point::new(13.0, 2.0, 3.0);
-    let lookat = Point::new(0.0, 0.0, 0.0);
-    let vup = Vec3::new(0.0, 1.0, 0.0);
-    let dist_to_focus = 10.0;
-    let aperture = 0.1;
-    let camera = Camera::new(
-        lookfrom,
-        lookat,
-        vup,
-        20.0,
-        aspect_ratio.ratio(),
-        aperture,
-        dist_to_focus,
-    );
+    let camera: Box<dyn Camera> = match stereo {
+        Some(rig) => Box::new(settings.stereo(rig, interocular, layout)),
+        None => settings.camera(),
+    };
 
     // Render
     println!("P3\n{} {}\n255", image_width, image_height);
@@ -52,13 +200,80 @@ pub fn process() {
     for j in (0..image_height).rev() {
         for i in 0..image_width {
             let mut color = Color::default();
-            for _ in 0..samples_per_pixel {
-                let u = (i as f64 + rng.gen::<f64>()) / (image_width - 1) as f64;
-                let v = (j as f64 + rng.gen::<f64>()) / (image_height - 1) as f64;
-                let ray = camera.ray(u, v, &mut rng);
-                color += ray.color(&world, &mut rng, max_depth);
+            let mut xyz = Vec3::default();
+            for s in 0..samples_per_pixel {
+                let (u, v) = pixel_sample(i, j, image_width, image_height, &mut rng);
+                let mut ray = match camera.ray(u, v, &mut rng) {
+                    Some(ray) => ray,
+                    None => continue,
+                };
+                if spectral {
+                    let wavelength = sample_wavelength_stratified(&mut rng, s, samples_per_pixel);
+                    ray.wavelength = Some(wavelength);
+                    let radiance = ray.spectral_radiance(&world, &mut rng, max_depth);
+                    xyz += radiance_to_xyz(radiance, wavelength);
+                } else if let Some(fog) = &fog {
+                    color += fog.color(&ray, &world, &mut rng, max_depth);
+                } else {
+                    color += ray.color(&world, &mut rng, max_depth);
+                }
+            }
+            if spectral {
+                color = xyz_to_rgb(&xyz);
             }
             println!("{}", color.to_string(samples_per_pixel as f64));
         }
     }
 }
+
+#[test]
+fn test_stereo_seam() {
+    use crate::{
+        camera::{PerspectiveCamera, StereoCamera},
+        hittable_list::HittableList,
+        sphere::Sphere,
+    };
+
+    // The left eye looks straight down at the white end of the sky, the right eye straight up
+    // at the blue end, so any sample from the wrong eye shows in a boundary pixel.
+    let eye = |lookat: Vec3| -> Box<dyn Camera> {
+        Box::new(PerspectiveCamera::new(
+            Point::default(),
+            lookat,
+            Vec3::new(1.0, 0.0, 0.0),
+            1.0,
+            1.0,
+            0.0,
+            1.0,
+        ))
+    };
+    let world: HittableList<Sphere> = HittableList::new();
+    let mut rng = rand::thread_rng();
+    let (width, height) = (8, 4);
+    for &layout in [StereoLayout::SideBySide, StereoLayout::TopBottom].iter() {
+        let camera = StereoCamera::new(
+            eye(Vec3::new(0.0, -1.0, 0.0)),
+            eye(Vec3::new(0.0, 1.0, 0.0)),
+            layout,
+        );
+        let (image_width, image_height) = layout.image_size(width, height);
+        let mut render = |i: usize, j: usize| {
+            let mut color = Color::default();
+            for _ in 0..100 {
+                let (u, v) = pixel_sample(i, j, image_width, image_height, &mut rng);
+                color += camera
+                    .ray(u, v, &mut rng)
+                    .unwrap()
+                    .color(&world, &mut rng, 1);
+            }
+            color / 100.0
+        };
+        let (left, right) = match layout {
+            StereoLayout::SideBySide => (render(width - 1, 0), render(width, 0)),
+            // The left eye is on top, and rows count from the bottom.
+            StereoLayout::TopBottom => (render(0, height), render(0, height - 1)),
+        };
+        assert!((left - Color::new(1.0, 1.0, 1.0)).length() < 1e-3);
+        assert!((right - Color::new(0.5, 0.7, 1.0)).length() < 1e-3);
+    }
+}
@@ -1,8 +1,37 @@
-mod camera;
-mod hittable;
-mod hittable_list;
+pub mod aabb;
+pub mod atmosphere;
+pub mod bvh;
+pub mod camera;
+pub mod constant_medium;
+pub mod csg;
+pub mod curve;
+pub mod displacement;
+pub mod gltf;
+pub mod grid_medium;
+pub mod hair;
+pub mod heightfield;
+pub mod hittable;
+pub mod hittable_list;
 pub mod io;
-mod material;
-mod ray;
-mod sphere;
-mod vec3;
+pub mod material;
+pub mod mesh;
+pub mod moving_sphere;
+pub mod onb;
+pub mod pbrt;
+pub mod plane;
+pub mod ply;
+pub mod point_cloud;
+pub mod polynomial;
+pub mod quad;
+pub mod quadric;
+pub mod ray;
+pub mod scene;
+pub mod sdf;
+pub mod spectrum;
+pub mod sphere;
+pub mod stl;
+pub mod subdivision;
+pub mod subsurface;
+pub mod texture;
+pub mod transform;
+pub mod vec3;
@@ -1,9 +1,13 @@
 use crate::{
     hittable::HitRecord,
+    onb::Onb,
     ray::Ray,
-    vec3::{Color, Vec3},
+    spectrum::{rgb_to_illuminant, rgb_to_reflectance, sample_wavelength, wavelength_to_rgb, Spd},
+    texture::{SolidColor, Texture},
+    vec3::{Color, Direction, Point, Vec3},
 };
 use rand::{Rng, RngCore};
+use std::rc::Rc;
 
 pub trait Material: CloneMaterial {
     fn scatter(
@@ -14,6 +18,12 @@ pub trait Material: CloneMaterial {
         scattered: &mut Ray,
         rng: &mut dyn RngCore,
     ) -> bool;
+    fn emitted(&self, _u: f64, _v: f64, _p: &Point) -> Color {
+        Color::default()
+    }
+    fn emitted_spectral(&self, u: f64, v: f64, p: &Point, wavelength: f64) -> f64 {
+        rgb_to_illuminant(&self.emitted(u, v, p), wavelength)
+    }
 }
 
 pub trait CloneMaterial {
@@ -37,11 +47,14 @@ impl Clone for Box<dyn Material> {
 
 #[derive(Clone)]
 pub struct Lambertian {
-    albedo: Color,
+    albedo: Rc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Self {
+        Self::new_texture(Rc::new(SolidColor::new(albedo)))
+    }
+    pub fn new_texture(albedo: Rc<dyn Texture>) -> Self {
         Self { albedo }
     }
 }
@@ -49,7 +62,7 @@ impl Lambertian {
 impl Material for Lambertian {
     fn scatter(
         &self,
-        _r_in: &Ray,
+        r_in: &Ray,
         rec: &HitRecord,
         attenuation: &mut Color,
         scattered: &mut Ray,
@@ -64,8 +77,10 @@ impl Material for Lambertian {
         *scattered = Ray {
             origin: rec.point,
             direction: scatter_direction,
+            wavelength: r_in.wavelength,
+            time: r_in.time,
         };
-        *attenuation = self.albedo;
+        *attenuation = self.albedo.value_at(rec);
         true
     }
 }
@@ -98,23 +113,87 @@ impl Material for Metal {
         *scattered = Ray {
             origin: rec.point,
             direction: reflected + self.fuzz * Vec3::new_random_in_unit_sphere(rng),
+            wavelength: r_in.wavelength,
+            time: r_in.time,
         };
         *attenuation = self.albedo;
         scattered.direction.dot(&rec.normal) > 0.0
     }
 }
 
+// Wavelength dependent index of refraction. Coefficients take wavelengths in micrometers.
+#[derive(Debug, Clone, Copy)]
+pub enum Dispersion {
+    Constant(f64),
+    // n = a + b / λ²
+    Cauchy { a: f64, b: f64 },
+    // n² = 1 + Σ bᵢ λ² / (λ² - cᵢ)
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    pub const BK7: Self = Self::Sellmeier {
+        b: [1.03961212, 0.231792344, 1.01046945],
+        c: [0.00600069867, 0.0200179144, 103.560653],
+    };
+    pub const CROWN_GLASS: Self = Self::Cauchy {
+        a: 1.5220,
+        b: 0.00459,
+    };
+    pub const FLINT_GLASS: Self = Self::Cauchy {
+        a: 1.7280,
+        b: 0.01342,
+    };
+    pub const DIAMOND: Self = Self::Sellmeier {
+        b: [0.3306, 4.3356, 0.0],
+        c: [0.030625, 0.011236, 0.0],
+    };
+
+    pub fn index_of_refraction(&self, wavelength: f64) -> f64 {
+        let l2 = (wavelength * 1e-3) * (wavelength * 1e-3);
+        match *self {
+            Self::Constant(n) => n,
+            Self::Cauchy { a, b } => a + b / l2,
+            Self::Sellmeier { b, c } => (1.0
+                + b.iter()
+                    .zip(c.iter())
+                    .map(|(b, c)| b * l2 / (l2 - c))
+                    .sum::<f64>())
+            .sqrt(),
+        }
+    }
+    pub fn is_dispersive(&self) -> bool {
+        !matches!(self, Self::Constant(_))
+    }
+}
+
 #[derive(Clone)]
 pub struct Dielectric {
-    index_of_refraction: f64,
+    dispersion: Dispersion,
+    absorption: Color,
 }
 
 impl Dielectric {
     pub fn new(index_of_refraction: f64) -> Self {
+        Self::new_absorbing(index_of_refraction, Color::default())
+    }
+    // `absorption` is the Beer-Lambert coefficient per unit distance travelled inside the medium.
+    pub fn new_absorbing(index_of_refraction: f64, absorption: Color) -> Self {
+        Self::new_dispersive(Dispersion::Constant(index_of_refraction), absorption)
+    }
+    pub fn new_dispersive(dispersion: Dispersion, absorption: Color) -> Self {
         Self {
-            index_of_refraction,
+            dispersion,
+            absorption,
         }
     }
+    pub fn transmittance(&self, distance: f64) -> Color {
+        Color::new(
+            (-self.absorption[0] * distance).exp(),
+            (-self.absorption[1] * distance).exp(),
+            (-self.absorption[2] * distance).exp(),
+        )
+    }
     pub fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
         // Use Schlick's approximation for reflectance.
         let r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
@@ -132,11 +211,27 @@ impl Material for Dielectric {
         scattered: &mut Ray,
         rng: &mut dyn RngCore,
     ) -> bool {
-        *attenuation = Color::new(1.0, 1.0, 1.0);
+        // A hit from the inside ends a path segment that travelled through the medium.
+        *attenuation = if rec.front_face {
+            Color::new(1.0, 1.0, 1.0)
+        } else {
+            self.transmittance((rec.point - r_in.origin).length())
+        };
+
+        // The first dispersive interaction picks the hero wavelength that the rest of the path follows.
+        let mut wavelength = r_in.wavelength;
+        if wavelength.is_none() && self.dispersion.is_dispersive() {
+            let lambda = sample_wavelength(rng);
+            *attenuation = *attenuation * wavelength_to_rgb(lambda);
+            wavelength = Some(lambda);
+        }
+        let index_of_refraction = self
+            .dispersion
+            .index_of_refraction(wavelength.unwrap_or(550.0));
         let refraction_ratio = if rec.front_face {
-            1.0 / self.index_of_refraction
+            1.0 / index_of_refraction
         } else {
-            self.index_of_refraction
+            index_of_refraction
         };
 
         let unit_direction = r_in.direction.unit();
@@ -162,7 +257,757 @@ impl Material for Dielectric {
         *scattered = Ray {
             origin: rec.point,
             direction,
+            wavelength,
+            time: r_in.time,
+        };
+        true
+    }
+}
+
+#[derive(Clone)]
+pub struct DiffuseLight {
+    emit: Rc<dyn Texture>,
+    spd: Option<(Spd, f64)>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Rc<dyn Texture>) -> Self {
+        Self { emit, spd: None }
+    }
+    pub fn new_spectral(spd: Spd, scale: f64) -> Self {
+        Self {
+            emit: Rc::new(SolidColor::new(scale * spd.to_rgb())),
+            spd: Some((spd, scale)),
+        }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _r_in: &Ray,
+        _rec: &HitRecord,
+        _attenuation: &mut Color,
+        _scattered: &mut Ray,
+        _rng: &mut dyn RngCore,
+    ) -> bool {
+        false
+    }
+    fn emitted(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.emit.value(u, v, p)
+    }
+    fn emitted_spectral(&self, u: f64, v: f64, p: &Point, wavelength: f64) -> f64 {
+        match self.spd {
+            Some((spd, scale)) => scale * spd.value(wavelength),
+            None => rgb_to_illuminant(&self.emitted(u, v, p), wavelength),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Isotropic {
+    albedo: Rc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Rc<dyn Texture>) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+        rng: &mut dyn RngCore,
+    ) -> bool {
+        *scattered = Ray {
+            origin: rec.point,
+            direction: Vec3::new_random_unit(rng),
+            wavelength: r_in.wavelength,
+            time: r_in.time,
+        };
+        *attenuation = self.albedo.value_at(rec);
+        true
+    }
+}
+
+// Henyey-Greenstein phase function; `g` > 0 scatters forward, `g` < 0 backward.
+#[derive(Clone)]
+pub struct HenyeyGreenstein {
+    albedo: Rc<dyn Texture>,
+    g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Rc<dyn Texture>, g: f64) -> Self {
+        Self {
+            albedo,
+            g: g.clamp(-0.99, 0.99),
+        }
+    }
+    pub fn phase(cos_theta: f64, g: f64) -> f64 {
+        let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * std::f64::consts::PI * denom * denom.sqrt())
+    }
+    // Samples a direction around the direction of travel `forward`.
+    pub fn sample(forward: &Vec3, g: f64, rng: &mut dyn RngCore) -> Vec3 {
+        let r1: f64 = rng.gen();
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * r1
+        } else {
+            let sqr_term = (1.0 - g * g) / (1.0 - g + 2.0 * g * r1);
+            (1.0 + g * g - sqr_term * sqr_term) / (2.0 * g)
+        }
+        .clamp(-1.0, 1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let phi = 2.0 * std::f64::consts::PI * rng.gen::<f64>();
+        Onb::new_from_w(forward).local(&Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ))
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+        rng: &mut dyn RngCore,
+    ) -> bool {
+        *scattered = Ray {
+            origin: rec.point,
+            direction: Self::sample(&r_in.direction, self.g, rng),
+            wavelength: r_in.wavelength,
+            time: r_in.time,
+        };
+        *attenuation = self.albedo.value_at(rec);
+        true
+    }
+}
+
+fn schlick_weight(cosine: f64) -> f64 {
+    (1.0 - cosine).clamp(0.0, 1.0).powf(5.0)
+}
+
+fn schlick_fresnel(f0: Color, cosine: f64) -> Color {
+    f0 + schlick_weight(cosine) * (Color::new(1.0, 1.0, 1.0) - f0)
+}
+
+// Samples a microfacet normal from the GGX distribution around `normal`.
+fn sample_ggx(normal: &Vec3, alpha: f64, rng: &mut dyn RngCore) -> Vec3 {
+    let phi = 2.0 * std::f64::consts::PI * rng.gen::<f64>();
+    let r2: f64 = rng.gen();
+    let cos_theta = ((1.0 - r2) / (1.0 + (alpha * alpha - 1.0) * r2)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    Onb::new_from_w(normal).local(&Vec3::new(
+        sin_theta * phi.cos(),
+        sin_theta * phi.sin(),
+        cos_theta,
+    ))
+}
+
+fn smith_g1(n_dot_x: f64, alpha: f64) -> f64 {
+    let a2 = alpha * alpha;
+    2.0 * n_dot_x / (n_dot_x + (a2 + (1.0 - a2) * n_dot_x * n_dot_x).sqrt())
+}
+
+// Throughput of a GGX reflection sampled proportionally to D(h) (n.h), without the Fresnel term.
+fn ggx_reflection_weight(n: &Vec3, v: &Vec3, l: &Vec3, h: &Vec3, alpha: f64) -> f64 {
+    let n_dot_v = n.dot(v).max(1e-6);
+    let n_dot_l = n.dot(l).max(1e-6);
+    let n_dot_h = n.dot(h).max(1e-6);
+    let v_dot_h = v.dot(h).abs();
+    smith_g1(n_dot_v, alpha) * smith_g1(n_dot_l, alpha) * v_dot_h / (n_dot_v * n_dot_h)
+}
+
+fn roughness_to_alpha(roughness: f64) -> f64 {
+    (roughness * roughness).max(1e-3)
+}
+
+// Disney-style uber-material. Scalar parameters are textures too; only their first channel is used.
+#[derive(Clone)]
+pub struct Principled {
+    base_color: Rc<dyn Texture>,
+    metallic: Rc<dyn Texture>,
+    roughness: Rc<dyn Texture>,
+    specular: Rc<dyn Texture>,
+    clearcoat: Rc<dyn Texture>,
+    clearcoat_gloss: Rc<dyn Texture>,
+    sheen: Rc<dyn Texture>,
+    sheen_tint: Rc<dyn Texture>,
+    transmission: Rc<dyn Texture>,
+    index_of_refraction: f64,
+    emission: Option<Rc<dyn Texture>>,
+}
+
+impl Principled {
+    pub fn new(base_color: Rc<dyn Texture>) -> Self {
+        Self {
+            base_color,
+            metallic: Rc::new(SolidColor::new_scalar(0.0)),
+            roughness: Rc::new(SolidColor::new_scalar(0.5)),
+            specular: Rc::new(SolidColor::new_scalar(0.5)),
+            clearcoat: Rc::new(SolidColor::new_scalar(0.0)),
+            clearcoat_gloss: Rc::new(SolidColor::new_scalar(1.0)),
+            sheen: Rc::new(SolidColor::new_scalar(0.0)),
+            sheen_tint: Rc::new(SolidColor::new_scalar(0.5)),
+            transmission: Rc::new(SolidColor::new_scalar(0.0)),
+            index_of_refraction: 1.5,
+            emission: None,
+        }
+    }
+    pub fn with_metallic(mut self, metallic: Rc<dyn Texture>) -> Self {
+        self.metallic = metallic;
+        self
+    }
+    pub fn with_roughness(mut self, roughness: Rc<dyn Texture>) -> Self {
+        self.roughness = roughness;
+        self
+    }
+    pub fn with_specular(mut self, specular: Rc<dyn Texture>) -> Self {
+        self.specular = specular;
+        self
+    }
+    pub fn with_clearcoat(mut self, clearcoat: Rc<dyn Texture>, gloss: Rc<dyn Texture>) -> Self {
+        self.clearcoat = clearcoat;
+        self.clearcoat_gloss = gloss;
+        self
+    }
+    pub fn with_sheen(mut self, sheen: Rc<dyn Texture>, tint: Rc<dyn Texture>) -> Self {
+        self.sheen = sheen;
+        self.sheen_tint = tint;
+        self
+    }
+    pub fn with_transmission(
+        mut self,
+        transmission: Rc<dyn Texture>,
+        index_of_refraction: f64,
+    ) -> Self {
+        self.transmission = transmission;
+        self.index_of_refraction = index_of_refraction;
+        self
+    }
+    pub fn with_emission(mut self, emission: Rc<dyn Texture>) -> Self {
+        self.emission = Some(emission);
+        self
+    }
+    fn scalar(texture: &Rc<dyn Texture>, rec: &HitRecord) -> f64 {
+        texture.value_at(rec)[0].clamp(0.0, 1.0)
+    }
+    fn scatter_inside(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        alpha: f64,
+        rng: &mut dyn RngCore,
+    ) -> Direction {
+        let unit_direction = r_in.direction.unit();
+        let h = sample_ggx(&rec.normal, alpha, rng);
+        let cos_theta = (-unit_direction).dot(&h).min(1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let refraction_ratio = self.index_of_refraction;
+        if refraction_ratio * sin_theta > 1.0
+            || Dielectric::reflectance(cos_theta, refraction_ratio) > rng.gen()
+        {
+            unit_direction.reflect(&h)
+        } else {
+            unit_direction.refract(&h, refraction_ratio)
+        }
+    }
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+        rng: &mut dyn RngCore,
+    ) -> bool {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let base_color = self.base_color.value_at(rec);
+        let metallic = Self::scalar(&self.metallic, rec);
+        let roughness = Self::scalar(&self.roughness, rec);
+        let specular = Self::scalar(&self.specular, rec);
+        let clearcoat = Self::scalar(&self.clearcoat, rec);
+        let clearcoat_gloss = Self::scalar(&self.clearcoat_gloss, rec);
+        let sheen = Self::scalar(&self.sheen, rec);
+        let sheen_tint = Self::scalar(&self.sheen_tint, rec);
+        let transmission = Self::scalar(&self.transmission, rec);
+        let alpha = roughness_to_alpha(roughness);
+
+        // Rays travelling inside a transmissive object only see the rough dielectric interface.
+        // Opaque surfaces are shaded alike from both sides; `rec.normal` already faces the ray.
+        if !rec.front_face && transmission > 0.0 {
+            *scattered = Ray {
+                origin: rec.point,
+                direction: self.scatter_inside(r_in, rec, alpha, rng),
+                wavelength: r_in.wavelength,
+                time: r_in.time,
+            };
+            *attenuation = white;
+            return true;
+        }
+
+        let n = rec.normal;
+        let v = -r_in.direction.unit();
+        let n_dot_v = n.dot(&v).clamp(1e-6, 1.0);
+
+        // Clearcoat: a colorless specular layer on top, chosen by its own Fresnel reflectance.
+        let clearcoat_probability =
+            0.25 * clearcoat * schlick_fresnel(Color::new(0.04, 0.04, 0.04), n_dot_v)[0];
+        if rng.gen::<f64>() < clearcoat_probability {
+            let clearcoat_alpha = (1.0 - clearcoat_gloss) * 0.1 + clearcoat_gloss * 0.001;
+            let h = sample_ggx(&n, clearcoat_alpha, rng);
+            let l = (-v).reflect(&h);
+            *scattered = Ray {
+                origin: rec.point,
+                direction: l,
+                wavelength: r_in.wavelength,
+                time: r_in.time,
+            };
+            *attenuation = ggx_reflection_weight(&n, &v, &l, &h, clearcoat_alpha) * white;
+            return l.dot(&n) > 0.0;
+        }
+
+        // Base layer: specular reflection vs. diffuse/transmission, split by the dielectric Fresnel.
+        let dielectric_f0 = 0.08 * specular;
+        let dielectric_fresnel = schlick_fresnel(
+            Color::new(dielectric_f0, dielectric_f0, dielectric_f0),
+            n_dot_v,
+        )[0];
+        let specular_probability = metallic + (1.0 - metallic) * dielectric_fresnel;
+        if rng.gen::<f64>() < specular_probability {
+            let h = sample_ggx(&n, alpha, rng);
+            let l = (-v).reflect(&h);
+            let v_dot_h = v.dot(&h).abs();
+            let fresnel = (1.0 - metallic)
+                * schlick_fresnel(
+                    Color::new(dielectric_f0, dielectric_f0, dielectric_f0),
+                    v_dot_h,
+                )
+                + metallic * schlick_fresnel(base_color, v_dot_h);
+            *scattered = Ray {
+                origin: rec.point,
+                direction: l,
+                wavelength: r_in.wavelength,
+                time: r_in.time,
+            };
+            *attenuation =
+                ggx_reflection_weight(&n, &v, &l, &h, alpha) / specular_probability * fresnel;
+            return l.dot(&n) > 0.0;
+        }
+
+        if rng.gen::<f64>() < transmission {
+            let h = sample_ggx(&n, alpha, rng);
+            let unit_direction = -v;
+            let refraction_ratio = 1.0 / self.index_of_refraction;
+            let cos_theta = v.dot(&h).min(1.0);
+            let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+            let direction = if refraction_ratio * sin_theta > 1.0 {
+                unit_direction.reflect(&h)
+            } else {
+                unit_direction.refract(&h, refraction_ratio)
+            };
+            *scattered = Ray {
+                origin: rec.point,
+                direction,
+                wavelength: r_in.wavelength,
+                time: r_in.time,
+            };
+            *attenuation = base_color;
+            return true;
+        }
+
+        let mut scatter_direction = n + Vec3::new_random_unit(rng);
+        if scatter_direction.is_near_zero() {
+            scatter_direction = n;
+        }
+        let l = scatter_direction.unit();
+        let h = (l + v).unit();
+        let n_dot_l = n.dot(&l).clamp(0.0, 1.0);
+        let l_dot_h = l.dot(&h).clamp(0.0, 1.0);
+
+        let fd90 = 0.5 + 2.0 * roughness * l_dot_h * l_dot_h;
+        let diffuse = (1.0 + (fd90 - 1.0) * schlick_weight(n_dot_l))
+            * (1.0 + (fd90 - 1.0) * schlick_weight(n_dot_v));
+        let tint = if base_color.luminance() > 0.0 {
+            base_color / base_color.luminance()
+        } else {
+            white
+        };
+        let sheen_color = (1.0 - sheen_tint) * white + sheen_tint * tint;
+        let sheen_term = std::f64::consts::PI * sheen * schlick_weight(l_dot_h) * sheen_color;
+
+        *scattered = Ray {
+            origin: rec.point,
+            direction: scatter_direction,
+            wavelength: r_in.wavelength,
+            time: r_in.time,
         };
+        *attenuation = diffuse * base_color + sheen_term;
         true
     }
+    fn emitted(&self, u: f64, v: f64, p: &Point) -> Color {
+        match &self.emission {
+            Some(emission) => emission.value(u, v, p),
+            None => Color::default(),
+        }
+    }
+}
+
+// Blends two materials by choosing one of them per scattering event.
+#[derive(Clone)]
+pub struct Mix {
+    first: Box<dyn Material>,
+    second: Box<dyn Material>,
+    weight: Rc<dyn Texture>,
+}
+
+impl Mix {
+    pub fn new(
+        first: Box<dyn Material>,
+        second: Box<dyn Material>,
+        weight: Rc<dyn Texture>,
+    ) -> Self {
+        Self {
+            first,
+            second,
+            weight,
+        }
+    }
+}
+
+impl Material for Mix {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+        rng: &mut dyn RngCore,
+    ) -> bool {
+        let weight = self.weight.value_at(rec)[0].clamp(0.0, 1.0);
+        if rng.gen::<f64>() < weight {
+            self.second.scatter(r_in, rec, attenuation, scattered, rng)
+        } else {
+            self.first.scatter(r_in, rec, attenuation, scattered, rng)
+        }
+    }
+    fn emitted(&self, u: f64, v: f64, p: &Point) -> Color {
+        let weight = self.weight.value(u, v, p)[0].clamp(0.0, 1.0);
+        (1.0 - weight) * self.first.emitted(u, v, p) + weight * self.second.emitted(u, v, p)
+    }
+    fn emitted_spectral(&self, u: f64, v: f64, p: &Point, wavelength: f64) -> f64 {
+        let weight = self.weight.value(u, v, p)[0].clamp(0.0, 1.0);
+        (1.0 - weight) * self.first.emitted_spectral(u, v, p, wavelength)
+            + weight * self.second.emitted_spectral(u, v, p, wavelength)
+    }
+}
+
+// A thin dielectric clearcoat over any base material.
+// `tint` is the transmittance of the coating for a single pass at normal incidence.
+#[derive(Clone)]
+pub struct Coated {
+    base: Box<dyn Material>,
+    index_of_refraction: f64,
+    roughness: f64,
+    tint: Color,
+}
+
+impl Coated {
+    pub fn new(
+        base: Box<dyn Material>,
+        index_of_refraction: f64,
+        roughness: f64,
+        tint: Color,
+    ) -> Self {
+        Self {
+            base,
+            index_of_refraction,
+            roughness,
+            tint,
+        }
+    }
+    fn cosine_inside(&self, cosine: f64) -> f64 {
+        let sin2 = (1.0 - cosine * cosine) / (self.index_of_refraction * self.index_of_refraction);
+        (1.0 - sin2).max(1e-6).sqrt()
+    }
+    fn tint_transmittance(&self, cosine: f64) -> Color {
+        let distance = 1.0 / self.cosine_inside(cosine);
+        Color::new(
+            self.tint[0].powf(distance),
+            self.tint[1].powf(distance),
+            self.tint[2].powf(distance),
+        )
+    }
+}
+
+impl Material for Coated {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+        rng: &mut dyn RngCore,
+    ) -> bool {
+        if !rec.front_face {
+            return self.base.scatter(r_in, rec, attenuation, scattered, rng);
+        }
+
+        let n = rec.normal;
+        let v = -r_in.direction.unit();
+        let alpha = roughness_to_alpha(self.roughness);
+        let h = sample_ggx(&n, alpha, rng);
+        let v_dot_h = v.dot(&h).clamp(0.0, 1.0);
+        if Dielectric::reflectance(v_dot_h, self.index_of_refraction) > rng.gen() {
+            let l = (-v).reflect(&h);
+            *scattered = Ray {
+                origin: rec.point,
+                direction: l,
+                wavelength: r_in.wavelength,
+                time: r_in.time,
+            };
+            *attenuation = ggx_reflection_weight(&n, &v, &l, &h, alpha) * Color::new(1.0, 1.0, 1.0);
+            return l.dot(&n) > 0.0;
+        }
+
+        if !self.base.scatter(r_in, rec, attenuation, scattered, rng) {
+            return false;
+        }
+        *attenuation = *attenuation * self.tint_transmittance(n.dot(&v));
+        let cos_out = scattered.direction.unit().dot(&n);
+        if cos_out > 0.0 {
+            // Light reflected internally at the top of the coating is not traced further.
+            *attenuation = *attenuation
+                * self.tint_transmittance(cos_out)
+                * (1.0 - Dielectric::reflectance(cos_out, self.index_of_refraction));
+        }
+        true
+    }
+    // Light from the base passes through the coating once, taken at normal incidence.
+    fn emitted(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.base.emitted(u, v, p) * self.tint_transmittance(1.0)
+    }
+    fn emitted_spectral(&self, u: f64, v: f64, p: &Point, wavelength: f64) -> f64 {
+        self.base.emitted_spectral(u, v, p, wavelength)
+            * rgb_to_reflectance(&self.tint_transmittance(1.0), wavelength)
+    }
+}
+
+#[test]
+fn test_dielectric_absorption() {
+    let glass = Dielectric::new_absorbing(1.0, Color::new(0.5, 0.0, 1.0));
+    let r_in = Ray {
+        origin: Vec3::new(0.0, 0.0, 0.0),
+        direction: Vec3::new(0.0, 0.0, 1.0),
+        wavelength: None,
+        time: 0.0,
+    };
+    let rec = HitRecord {
+        point: Vec3::new(0.0, 0.0, 2.0),
+        normal: Vec3::new(0.0, 0.0, -1.0),
+        t: 2.0,
+        front_face: false,
+        ..HitRecord::default()
+    };
+    let mut attenuation = Color::default();
+    let mut scattered = Ray::default();
+    let mut rng = rand::thread_rng();
+    assert!(glass.scatter(&r_in, &rec, &mut attenuation, &mut scattered, &mut rng));
+    assert!((attenuation[0] - (-1.0f64).exp()).abs() < 1e-12);
+    assert!((attenuation[1] - 1.0).abs() < 1e-12);
+    assert!((attenuation[2] - (-2.0f64).exp()).abs() < 1e-12);
+}
+
+#[test]
+fn test_dispersion() {
+    assert!((Dispersion::BK7.index_of_refraction(587.6) - 1.5168).abs() < 1e-3);
+    assert!((Dispersion::DIAMOND.index_of_refraction(589.3) - 2.417).abs() < 2e-3);
+    assert!(
+        Dispersion::FLINT_GLASS.index_of_refraction(450.0)
+            > Dispersion::FLINT_GLASS.index_of_refraction(650.0)
+    );
+    assert!((Dispersion::Constant(1.5).index_of_refraction(400.0) - 1.5).abs() < 1e-12);
+}
+
+#[test]
+fn test_henyey_greenstein_mean_cosine() {
+    let mut rng = rand::thread_rng();
+    let forward = Vec3::new(0.0, 0.0, 1.0);
+    let n = 100000;
+    let mean = (0..n)
+        .map(|_| HenyeyGreenstein::sample(&forward, 0.6, &mut rng).dot(&forward))
+        .sum::<f64>()
+        / n as f64;
+    assert!((mean - 0.6).abs() < 0.01);
+}
+
+#[test]
+fn test_principled() {
+    use crate::texture::ImageTexture;
+
+    let mut rng = rand::thread_rng();
+    let r_in = Ray {
+        origin: Vec3::new(0.0, 0.0, 0.0),
+        direction: Vec3::new(0.0, 0.0, 1.0),
+        ..Ray::default()
+    };
+    // A back face seen from outside single-sided geometry, and a front face.
+    let back = HitRecord {
+        point: Vec3::new(0.0, 0.0, 2.0),
+        normal: Vec3::new(0.0, 0.0, -1.0),
+        t: 2.0,
+        front_face: false,
+        ..HitRecord::default()
+    };
+    let front = HitRecord {
+        front_face: true,
+        ..back.clone()
+    };
+    let mut attenuation = Color::default();
+    let mut scattered = Ray::default();
+    let mut directions = |material: &Principled, rec: &HitRecord| {
+        (0..1000)
+            .filter_map(|_| {
+                if material.scatter(&r_in, rec, &mut attenuation, &mut scattered, &mut rng) {
+                    Some((scattered.direction.unit(), attenuation))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // Opaque surfaces never let light through, whichever side is hit.
+    let opaque = Principled::new(Rc::new(SolidColor::new(Color::new(0.8, 0.2, 0.2))));
+    for rec in [&back, &front].iter() {
+        let scattered = directions(&opaque, rec);
+        assert!(!scattered.is_empty());
+        assert!(scattered.iter().all(|(d, _)| d.dot(&rec.normal) >= -1e-9));
+    }
+
+    // Fully transmissive smooth glass mostly refracts on both sides.
+    let glass = Principled::new(Rc::new(SolidColor::new_scalar(1.0)))
+        .with_roughness(Rc::new(SolidColor::new_scalar(0.0)))
+        .with_transmission(Rc::new(SolidColor::new_scalar(1.0)), 1.5);
+    for rec in [&back, &front].iter() {
+        let scattered = directions(&glass, rec);
+        let through = scattered
+            .iter()
+            .filter(|(d, _)| d.dot(&rec.normal) < 0.0)
+            .count();
+        assert!(through > 900);
+    }
+
+    // Textured parameters are looked up at the hit: a red mirror on the left half of the
+    // texture space and a rough blue metal on the right.
+    let halves = |left: Color, right: Color| -> Rc<dyn Texture> {
+        Rc::new(ImageTexture::new(2, 1, vec![left, right]))
+    };
+    let metal = Principled::new(halves(Color::new(1.0, 0.0, 0.0), Color::new(0.0, 0.0, 1.0)))
+        .with_metallic(Rc::new(SolidColor::new_scalar(1.0)))
+        .with_roughness(halves(Color::default(), Color::new(1.0, 1.0, 1.0)));
+    let at = |u: f64| HitRecord {
+        u,
+        v: 0.5,
+        ..front.clone()
+    };
+    let mirror = directions(&metal, &at(0.25));
+    let near_mirror = mirror
+        .iter()
+        .filter(|(d, _)| (*d - Vec3::new(0.0, 0.0, -1.0)).length() < 0.05)
+        .count();
+    assert!(near_mirror > 950);
+    assert!(mirror.iter().all(|(_, a)| a[0] > 0.5 && a[2] < 0.1));
+    let rough = directions(&metal, &at(0.75));
+    assert!(rough.iter().all(|(_, a)| a[2] > a[0]));
+    assert!(rough
+        .iter()
+        .any(|(d, _)| (*d - Vec3::new(0.0, 0.0, -1.0)).length() > 0.2));
+}
+
+#[test]
+fn test_mix_and_coated() {
+    use crate::texture::SolidColor;
+
+    let mut rng = rand::thread_rng();
+    let r_in = Ray {
+        direction: Vec3::new(0.0, 0.0, 1.0),
+        ..Ray::default()
+    };
+    let rec = HitRecord {
+        point: Vec3::new(0.0, 0.0, 2.0),
+        normal: Vec3::new(0.0, 0.0, -1.0),
+        t: 2.0,
+        front_face: true,
+        ..HitRecord::default()
+    };
+    let (red, blue) = (Color::new(1.0, 0.0, 0.0), Color::new(0.0, 0.0, 1.0));
+    let n = 20000;
+    // Mean attenuation over many scatters, counting absorbed paths as black.
+    let mut albedo = |material: &dyn Material| {
+        let mut sum = Color::default();
+        for _ in 0..n {
+            let (mut attenuation, mut scattered) = (Color::default(), Ray::default());
+            if material.scatter(&r_in, &rec, &mut attenuation, &mut scattered, &mut rng) {
+                sum += attenuation;
+            }
+        }
+        sum / n as f64
+    };
+
+    // The weight picks the second material with its own probability.
+    let mix = |weight: f64| {
+        Mix::new(
+            Box::new(Lambertian::new(red)),
+            Box::new(Lambertian::new(blue)),
+            Rc::new(SolidColor::new_scalar(weight)),
+        )
+    };
+    assert!((albedo(&mix(0.0)) - red).length() < 1e-12);
+    assert!((albedo(&mix(1.0)) - blue).length() < 1e-12);
+    let quarter = albedo(&mix(0.25));
+    assert!((quarter[0] - 0.75).abs() < 0.02 && (quarter[2] - 0.25).abs() < 0.02);
+
+    // A clear coat over a white base neither creates energy nor loses more than the light
+    // trapped under it; over a black base only the coat's own Fresnel reflection is left.
+    let coated =
+        |base: Color, tint: Color| Coated::new(Box::new(Lambertian::new(base)), 1.5, 0.1, tint);
+    let white = Color::new(1.0, 1.0, 1.0);
+    let clear = albedo(&coated(white, white));
+    for i in 0..3 {
+        assert!(clear[i] < 1.0 + 0.02 && clear[i] > 0.7);
+    }
+    let tinted = albedo(&coated(white, Color::new(0.5, 0.5, 0.5)));
+    assert!(tinted[0] < 0.5 * clear[0]);
+    let black = albedo(&coated(Color::default(), white));
+    assert!(black[0] > 0.02 && black[0] < 0.1);
+
+    // Lights stay lit when mixed or coated.
+    let p = Point::default();
+    let light = || Box::new(DiffuseLight::new(Rc::new(SolidColor::new(4.0 * red))));
+    let mixed_light = Mix::new(
+        light(),
+        Box::new(Lambertian::new(blue)),
+        Rc::new(SolidColor::new_scalar(0.25)),
+    );
+    assert!((mixed_light.emitted(0.0, 0.0, &p) - 3.0 * red).length() < 1e-12);
+    let spectral = light().emitted_spectral(0.0, 0.0, &p, 650.0);
+    assert!(spectral > 0.0);
+    assert!((mixed_light.emitted_spectral(0.0, 0.0, &p, 650.0) - 0.75 * spectral).abs() < 1e-12);
+    let coated_light = Coated::new(light(), 1.5, 0.1, Color::new(0.5, 1.0, 1.0));
+    assert!((coated_light.emitted(0.0, 0.0, &p) - 2.0 * red).length() < 1e-12);
+    let tint = rgb_to_reflectance(&Color::new(0.5, 1.0, 1.0), 650.0);
+    assert!((coated_light.emitted_spectral(0.0, 0.0, &p, 650.0) - tint * spectral).abs() < 1e-12);
 }
@@ -0,0 +1,216 @@
+use crate::{
+    aabb::Aabb,
+    bvh::Bvh,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    ray::Ray,
+    transform::Transform,
+    vec3::{Color, Point, Vec3},
+};
+use std::rc::Rc;
+
+// Indexed triangles with optional per-vertex attributes. Each attribute is either empty or
+// has one entry per position.
+#[derive(Debug, Clone, Default)]
+pub struct TriangleMesh {
+    pub positions: Vec<Point>,
+    pub normals: Vec<Vec3>,
+    pub colors: Vec<Color>,
+    pub uvs: Vec<(f64, f64)>,
+    pub indices: Vec<[usize; 3]>,
+}
+
+impl TriangleMesh {
+    pub fn bounds(&self) -> Aabb {
+        Aabb::from_points(&self.positions)
+    }
+    // Bakes a transform into positions and normals.
+    pub fn transform(&mut self, transform: &Transform) {
+        for p in self.positions.iter_mut() {
+            *p = transform.transform_point(p);
+        }
+        for n in self.normals.iter_mut() {
+            *n = transform.transform_normal(n).unit();
+        }
+        if transform.swaps_handedness() {
+            for t in self.indices.iter_mut() {
+                t.swap(1, 2);
+            }
+        }
+    }
+    // Replaces the normals by area-weighted averages over the triangles around each vertex.
+    pub fn compute_normals(&mut self) {
+        let p = &self.positions;
+        let mut normals = vec![Vec3::default(); p.len()];
+        for &[a, b, c] in self.indices.iter() {
+            let n = (p[b] - p[a]).cross(&(p[c] - p[a]));
+            normals[a] += n;
+            normals[b] += n;
+            normals[c] += n;
+        }
+        for n in normals.iter_mut() {
+            if !n.is_near_zero() {
+                *n = n.unit();
+            }
+        }
+        self.normals = normals;
+    }
+    // Drops attributes whose length does not match the positions and triangles that refer
+    // to missing vertices, so later lookups cannot go out of range.
+    pub fn validate(&mut self) {
+        let n = self.positions.len();
+        if self.normals.len() != n {
+            self.normals.clear();
+        }
+        if self.colors.len() != n {
+            self.colors.clear();
+        }
+        if self.uvs.len() != n {
+            self.uvs.clear();
+        }
+        self.indices.retain(|t| t.iter().all(|&i| i < n));
+    }
+}
+
+#[derive(Clone)]
+pub struct Triangle {
+    mesh: Rc<TriangleMesh>,
+    material: Rc<dyn Material>,
+    index: usize,
+    single_sided: bool,
+}
+
+impl Triangle {
+    fn vertices(&self) -> [usize; 3] {
+        self.mesh.indices[self.index]
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        // Möller-Trumbore.
+        let [i0, i1, i2] = self.vertices();
+        let p0 = self.mesh.positions[i0];
+        let edge1 = self.mesh.positions[i1] - p0;
+        let edge2 = self.mesh.positions[i2] - p0;
+        let pvec = r.direction.cross(&edge2);
+        let det = edge1.dot(&pvec);
+        if det.abs() < 1e-12 {
+            return false;
+        }
+        let inv_det = 1.0 / det;
+        let tvec = r.origin - p0;
+        let b1 = tvec.dot(&pvec) * inv_det;
+        if !(0.0..=1.0).contains(&b1) {
+            return false;
+        }
+        let qvec = tvec.cross(&edge1);
+        let b2 = r.direction.dot(&qvec) * inv_det;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return false;
+        }
+        let t = edge2.dot(&qvec) * inv_det;
+        if t < t_min || t_max < t {
+            return false;
+        }
+        let b0 = 1.0 - b1 - b2;
+        let interpolate = |a: Vec3, b: Vec3, c: Vec3| a * b0 + b * b1 + c * b2;
+
+        let mut geometric = edge1.cross(&edge2).unit();
+        let shading = if self.mesh.normals.is_empty() {
+            geometric
+        } else {
+            let n = &self.mesh.normals;
+            let shading = interpolate(n[i0], n[i1], n[i2]);
+            if shading.is_near_zero() {
+                geometric
+            } else {
+                // Vertex normals decide which side is outside.
+                if geometric.dot(&shading) < 0.0 {
+                    geometric = -geometric;
+                }
+                shading.unit()
+            }
+        };
+        let front_face = r.direction.dot(&geometric) < 0.0;
+        if self.single_sided && !front_face {
+            return false;
+        }
+        rec.t = t;
+        rec.point = r.at(t);
+        rec.front_face = front_face;
+        rec.normal = if rec.front_face { shading } else { -shading };
+        let (u, v) = if self.mesh.uvs.is_empty() {
+            (b1, b2)
+        } else {
+            let uv = &self.mesh.uvs;
+            (
+                uv[i0].0 * b0 + uv[i1].0 * b1 + uv[i2].0 * b2,
+                uv[i0].1 * b0 + uv[i1].1 * b1 + uv[i2].1 * b2,
+            )
+        };
+        rec.u = u;
+        rec.v = v;
+        rec.vertex_color = if self.mesh.colors.is_empty() {
+            None
+        } else {
+            let c = &self.mesh.colors;
+            Some(interpolate(c[i0], c[i1], c[i2]))
+        };
+        rec.material = self.material.clone();
+        true
+    }
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        let [i0, i1, i2] = self.vertices();
+        let p = &self.mesh.positions;
+        *output_box = Aabb::from_points(&[p[i0], p[i1], p[i2]]).padded(1e-4);
+        true
+    }
+}
+
+// A triangle mesh with a single material, accelerated by its own BVH.
+#[derive(Clone)]
+pub struct Mesh {
+    bvh: Bvh<Triangle>,
+}
+
+impl Mesh {
+    pub fn new(mesh: TriangleMesh, material: Rc<dyn Material>) -> Self {
+        Self::build(mesh, material, false)
+    }
+    // A mesh whose triangles can only be hit from the front, as given by the winding order or
+    // the vertex normals.
+    pub fn new_single_sided(mesh: TriangleMesh, material: Rc<dyn Material>) -> Self {
+        Self::build(mesh, material, true)
+    }
+    fn build(mut mesh: TriangleMesh, material: Rc<dyn Material>, single_sided: bool) -> Self {
+        mesh.validate();
+        let mesh = Rc::new(mesh);
+        let triangles = (0..mesh.indices.len())
+            .map(|index| Triangle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                index,
+                single_sided,
+            })
+            .collect();
+        Self {
+            bvh: Bvh::new(triangles, 0.0, 0.0),
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.bvh.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.bvh.is_empty()
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        self.bvh.hit(r, t_min, t_max, rec)
+    }
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        self.bvh.bounding_box(time0, time1, output_box)
+    }
+}
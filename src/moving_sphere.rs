@@ -0,0 +1,94 @@
+use crate::{
+    aabb::{surrounding_box, Aabb},
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    ray::Ray,
+    sphere::Sphere,
+    vec3::{Point, Vec3},
+};
+use std::rc::Rc;
+
+// A sphere moving linearly from center0 at time0 to center1 at time1.
+#[derive(Clone)]
+pub struct MovingSphere {
+    center0: Point,
+    center1: Point,
+    time0: f64,
+    time1: f64,
+    radius: f64,
+    material: Rc<dyn Material>,
+}
+
+impl MovingSphere {
+    pub fn new(
+        center0: Point,
+        center1: Point,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+        material: Rc<dyn Material>,
+    ) -> Self {
+        Self {
+            center0,
+            center1,
+            time0,
+            time1,
+            radius,
+            material,
+        }
+    }
+    pub fn center(&self, time: f64) -> Point {
+        if self.time1 <= self.time0 {
+            return self.center0;
+        }
+        self.center0
+            + ((time - self.time0) / (self.time1 - self.time0)) * (self.center1 - self.center0)
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        Sphere::new(self.center(r.time), self.radius, self.material.clone())
+            .hit(r, t_min, t_max, rec)
+    }
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        let box0 = Aabb::new(self.center(time0) - r, self.center(time0) + r);
+        let box1 = Aabb::new(self.center(time1) - r, self.center(time1) + r);
+        *output_box = surrounding_box(&box0, &box1);
+        true
+    }
+}
+
+#[test]
+fn test_moving_sphere() {
+    use crate::{material::Lambertian, vec3::Color};
+
+    let material = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let sphere = MovingSphere::new(
+        Point::new(0.0, 0.0, 0.0),
+        Point::new(4.0, 0.0, 0.0),
+        1.0,
+        2.0,
+        0.5,
+        material,
+    );
+    assert!((sphere.center(1.5) - Point::new(2.0, 0.0, 0.0)).length() < 1e-12);
+
+    // The box covers the sphere at both ends of the requested interval.
+    let mut bounds = Aabb::default();
+    assert!(sphere.bounding_box(1.0, 1.5, &mut bounds));
+    assert!((bounds.min() - Point::new(-0.5, -0.5, -0.5)).length() < 1e-12);
+    assert!((bounds.max() - Point::new(2.5, 0.5, 0.5)).length() < 1e-12);
+
+    let ray = |time: f64| Ray {
+        origin: Point::new(4.0, 0.0, 5.0),
+        direction: Vec3::new(0.0, 0.0, -1.0),
+        time,
+        ..Ray::default()
+    };
+    let mut rec = HitRecord::default();
+    assert!(!sphere.hit(&ray(1.0), 0.001, f64::INFINITY, &mut rec));
+    assert!(sphere.hit(&ray(2.0), 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 4.5).abs() < 1e-12);
+}
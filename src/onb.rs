@@ -0,0 +1,35 @@
+use crate::vec3::Vec3;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Onb {
+    axis: [Vec3; 3],
+}
+
+impl Onb {
+    pub fn new_from_w(n: &Vec3) -> Self {
+        let w = n.unit();
+        let a = if w[0].abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = w.cross(&a).unit();
+        let u = w.cross(&v);
+        Self { axis: [u, v, w] }
+    }
+    pub fn u(&self) -> Vec3 {
+        self.axis[0]
+    }
+    pub fn v(&self) -> Vec3 {
+        self.axis[1]
+    }
+    pub fn w(&self) -> Vec3 {
+        self.axis[2]
+    }
+    pub fn local(&self, a: &Vec3) -> Vec3 {
+        a[0] * self.u() + a[1] * self.v() + a[2] * self.w()
+    }
+    pub fn to_local(&self, a: &Vec3) -> Vec3 {
+        Vec3::new(a.dot(&self.u()), a.dot(&self.v()), a.dot(&self.w()))
+    }
+}
@@ -0,0 +1,797 @@
+use crate::{
+    camera::{CameraSettings, Projection},
+    material::{Coated, Dielectric, DiffuseLight, Lambertian, Material, Principled},
+    mesh::{Mesh, TriangleMesh},
+    scene::{Light, Scene},
+    spectrum::Spd,
+    sphere::Sphere,
+    texture::SolidColor,
+    transform::{Matrix4, Transform, Transformed},
+    vec3::{Color, Point, Vec3},
+};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+impl Scene {
+    // Loads a scene in a subset of the pbrt-v3 format. Anything outside the subset is skipped
+    // and reported in `warnings`.
+    pub fn load_pbrt<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut parser = Parser::new();
+        parser.includes.push(fs::canonicalize(path)?);
+        parser.parse(&text, directory)?;
+        Ok(parser.finish())
+    }
+    // File names in the scene are relative to `directory`.
+    pub fn read_pbrt(text: &str, directory: &Path) -> io::Result<Self> {
+        let mut parser = Parser::new();
+        parser.parse(text, directory)?;
+        Ok(parser.finish())
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Number(f64),
+    Open,
+    Close,
+}
+
+fn tokenize(text: &str) -> io::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '#' {
+            while chars.next().is_some_and(|c| c != '\n') {}
+        } else if c == '[' || c == ']' {
+            chars.next();
+            tokens.push(if c == '[' { Token::Open } else { Token::Close });
+        } else if c == '"' {
+            chars.next();
+            let mut s = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => s.push(c),
+                    None => return Err(invalid("unterminated string".to_string())),
+                }
+            }
+            tokens.push(Token::Str(s));
+        } else {
+            let mut s = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || "[]\"#".contains(c) {
+                    break;
+                }
+                s.push(c);
+                chars.next();
+            }
+            tokens.push(match s.parse::<f64>() {
+                Ok(x) => Token::Number(x),
+                Err(_) => Token::Word(s),
+            });
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Number(f64),
+    Str(String),
+    List(Vec<Value>),
+}
+
+// A typed parameter such as `"rgb Kd" [0.5 0.5 0.5]`.
+#[derive(Debug, Clone)]
+struct Param {
+    ty: String,
+    name: String,
+    numbers: Vec<f64>,
+    strings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Params(Vec<Param>);
+
+impl Params {
+    fn parse(values: &[Value]) -> io::Result<Self> {
+        let mut params = Vec::new();
+        for pair in values.chunks(2) {
+            let declaration = match &pair[0] {
+                Value::Str(s) => s,
+                value => return Err(invalid(format!("expected a parameter, found {:?}", value))),
+            };
+            let mut words = declaration.split_whitespace();
+            let (ty, name) = match (words.next(), words.next()) {
+                (Some(ty), Some(name)) => (ty.to_string(), name.to_string()),
+                _ => return Err(invalid(format!("malformed parameter \"{}\"", declaration))),
+            };
+            let items = match pair.get(1) {
+                Some(Value::List(items)) => items.clone(),
+                Some(value) => vec![value.clone()],
+                None => return Err(invalid(format!("parameter \"{}\" has no value", name))),
+            };
+            let mut param = Param {
+                ty,
+                name,
+                numbers: Vec::new(),
+                strings: Vec::new(),
+            };
+            for item in items {
+                match item {
+                    Value::Number(x) => param.numbers.push(x),
+                    Value::Str(s) => param.strings.push(s),
+                    Value::List(_) => return Err(invalid("nested parameter lists".to_string())),
+                }
+            }
+            params.push(param);
+        }
+        Ok(Self(params))
+    }
+    fn get(&self, name: &str) -> Option<&Param> {
+        self.0.iter().find(|p| p.name == name)
+    }
+    fn float(&self, name: &str, default: f64) -> f64 {
+        self.get(name)
+            .and_then(|p| p.numbers.first().copied())
+            .unwrap_or(default)
+    }
+    fn floats(&self, name: &str) -> Option<&[f64]> {
+        self.get(name).map(|p| p.numbers.as_slice())
+    }
+    fn string(&self, name: &str) -> Option<&str> {
+        self.get(name)
+            .and_then(|p| p.strings.first().map(|s| s.as_str()))
+    }
+    fn point(&self, name: &str, default: Point) -> Point {
+        match self.floats(name) {
+            Some(&[x, y, z, ..]) => Point::new(x, y, z),
+            _ => default,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct GraphicsState {
+    ctm: Transform,
+    material: Rc<dyn Material>,
+    area_light: Option<Color>,
+}
+
+struct Parser {
+    scene: Scene,
+    state: GraphicsState,
+    // Saved states; true for TransformBegin, which only restores the transform.
+    stack: Vec<(GraphicsState, bool)>,
+    named_materials: HashMap<String, Rc<dyn Material>>,
+    coordinate_systems: HashMap<String, Transform>,
+    camera: (Transform, String, Params),
+    film: (usize, usize),
+    // pbrt's camera is left handed; mirroring the world across the camera's vertical plane
+    // keeps images the same way round with this crate's right-handed camera.
+    mirror: Transform,
+    in_world: bool,
+    in_object: bool,
+    // Canonical paths of the files being parsed, outermost first.
+    includes: Vec<PathBuf>,
+}
+
+impl Parser {
+    fn new() -> Self {
+        Self {
+            scene: Scene::default(),
+            state: GraphicsState {
+                ctm: Transform::identity(),
+                material: Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+                area_light: None,
+            },
+            stack: Vec::new(),
+            named_materials: HashMap::new(),
+            coordinate_systems: HashMap::new(),
+            camera: (
+                Transform::identity(),
+                "perspective".to_string(),
+                Params::default(),
+            ),
+            film: (1280, 720),
+            mirror: Transform::identity(),
+            in_world: false,
+            in_object: false,
+            includes: Vec::new(),
+        }
+    }
+    fn finish(mut self) -> Scene {
+        if !self.in_world {
+            self.warn("file has no WorldBegin".to_string());
+        }
+        self.scene
+    }
+    fn warn(&mut self, message: String) {
+        if !self.scene.warnings.contains(&message) {
+            self.scene.warnings.push(message);
+        }
+    }
+    fn parse(&mut self, text: &str, directory: &Path) -> io::Result<()> {
+        let tokens = tokenize(text)?;
+        let mut i = 0;
+        while i < tokens.len() {
+            let directive = match &tokens[i] {
+                Token::Word(word) => word.clone(),
+                token => return Err(invalid(format!("expected a directive, found {:?}", token))),
+            };
+            i += 1;
+            let mut values = Vec::new();
+            while i < tokens.len() {
+                match &tokens[i] {
+                    Token::Word(_) => break,
+                    Token::Number(x) => values.push(Value::Number(*x)),
+                    Token::Str(s) => values.push(Value::Str(s.clone())),
+                    Token::Open => {
+                        let mut items = Vec::new();
+                        i += 1;
+                        loop {
+                            match tokens.get(i) {
+                                Some(Token::Number(x)) => items.push(Value::Number(*x)),
+                                Some(Token::Str(s)) => items.push(Value::Str(s.clone())),
+                                Some(Token::Close) => break,
+                                _ => return Err(invalid("unterminated list".to_string())),
+                            }
+                            i += 1;
+                        }
+                        values.push(Value::List(items));
+                    }
+                    Token::Close => return Err(invalid("unexpected ]".to_string())),
+                }
+                i += 1;
+            }
+            self.directive(&directive, &values, directory)?;
+        }
+        Ok(())
+    }
+    fn directive(&mut self, directive: &str, values: &[Value], directory: &Path) -> io::Result<()> {
+        let numbers: Vec<f64> = values
+            .iter()
+            .flat_map(|v| match v {
+                Value::Number(x) => vec![*x],
+                Value::List(items) => items
+                    .iter()
+                    .filter_map(|item| match item {
+                        Value::Number(x) => Some(*x),
+                        _ => None,
+                    })
+                    .collect(),
+                Value::Str(_) => vec![],
+            })
+            .collect();
+        let expect = |count: usize| -> io::Result<()> {
+            if numbers.len() == count {
+                Ok(())
+            } else {
+                Err(invalid(format!("{} expects {} numbers", directive, count)))
+            }
+        };
+        // The leading string of directives like `Shape "sphere" ...` and its parameters.
+        let named = || -> io::Result<(String, Params)> {
+            match values.split_first() {
+                Some((Value::Str(name), rest)) => Ok((name.clone(), Params::parse(rest)?)),
+                _ => Err(invalid(format!("{} expects a name", directive))),
+            }
+        };
+        match directive {
+            "Identity" => self.state.ctm = Transform::identity(),
+            "Translate" => {
+                expect(3)?;
+                let t = Transform::translate(Vec3::new(numbers[0], numbers[1], numbers[2]));
+                self.concat(t);
+            }
+            "Scale" => {
+                expect(3)?;
+                match Transform::scale(numbers[0], numbers[1], numbers[2]) {
+                    Some(t) => self.concat(t),
+                    None => self.warn("degenerate Scale is ignored".to_string()),
+                }
+            }
+            "Rotate" => {
+                expect(4)?;
+                let axis = Vec3::new(numbers[1], numbers[2], numbers[3]);
+                self.concat(Transform::rotate(numbers[0], axis));
+            }
+            "LookAt" => {
+                expect(9)?;
+                let n = &numbers;
+                let look_at = Transform::look_at(
+                    Point::new(n[0], n[1], n[2]),
+                    Point::new(n[3], n[4], n[5]),
+                    Vec3::new(n[6], n[7], n[8]),
+                );
+                match look_at {
+                    Some(camera_to_world) => self.concat(camera_to_world.inverse()),
+                    None => self.warn("degenerate LookAt is ignored".to_string()),
+                }
+            }
+            "Transform" | "ConcatTransform" => {
+                expect(16)?;
+                // Matrices are written column by column.
+                let mut m = [[0.0; 4]; 4];
+                for (i, row) in m.iter_mut().enumerate() {
+                    for (j, x) in row.iter_mut().enumerate() {
+                        *x = numbers[j * 4 + i];
+                    }
+                }
+                match Transform::new(Matrix4::new(m)) {
+                    Some(t) if directive == "Transform" => self.state.ctm = t,
+                    Some(t) => self.concat(t),
+                    None => self.warn(format!("singular {} is ignored", directive)),
+                }
+            }
+            "CoordinateSystem" => {
+                let (name, _) = named()?;
+                self.coordinate_systems.insert(name, self.state.ctm);
+            }
+            "CoordSysTransform" => {
+                let (name, _) = named()?;
+                match self.coordinate_systems.get(&name) {
+                    Some(t) => self.state.ctm = *t,
+                    None => self.warn(format!("unknown coordinate system \"{}\"", name)),
+                }
+            }
+            "Camera" => {
+                let (ty, params) = named()?;
+                self.coordinate_systems
+                    .insert("camera".to_string(), self.state.ctm.inverse());
+                self.camera = (self.state.ctm, ty, params);
+            }
+            "Film" => {
+                let (_, params) = named()?;
+                if params.get("cropwindow").is_some() {
+                    self.warn("Film cropwindow is ignored".to_string());
+                }
+                self.film = (
+                    params.float("xresolution", 1280.0).max(1.0) as usize,
+                    params.float("yresolution", 720.0).max(1.0) as usize,
+                );
+            }
+            "WorldBegin" => self.world_begin(),
+            "WorldEnd" => {}
+            "AttributeBegin" | "TransformBegin" => self
+                .stack
+                .push((self.state.clone(), directive == "TransformBegin")),
+            "AttributeEnd" | "TransformEnd" => match self.stack.pop() {
+                Some((state, true)) => self.state.ctm = state.ctm,
+                Some((state, false)) => self.state = state,
+                None => self.warn(format!("unmatched {}", directive)),
+            },
+            "Material" => {
+                let (ty, params) = named()?;
+                self.state.material = self.material(&ty, &params);
+            }
+            "MakeNamedMaterial" => {
+                let (name, params) = named()?;
+                let ty = params.string("type").unwrap_or("matte").to_string();
+                let material = self.material(&ty, &params);
+                self.named_materials.insert(name, material);
+            }
+            "NamedMaterial" => {
+                let (name, _) = named()?;
+                match self.named_materials.get(&name) {
+                    Some(material) => self.state.material = material.clone(),
+                    None => self.warn(format!("unknown named material \"{}\"", name)),
+                }
+            }
+            "AreaLightSource" => {
+                let (ty, params) = named()?;
+                if ty == "diffuse" {
+                    let scale = self.color(&params, "scale", Color::new(1.0, 1.0, 1.0));
+                    let radiance = self.color(&params, "L", Color::new(1.0, 1.0, 1.0));
+                    self.state.area_light = Some(radiance * scale);
+                } else {
+                    self.warn(format!("AreaLightSource \"{}\" is not supported", ty));
+                }
+            }
+            "LightSource" => {
+                let (ty, params) = named()?;
+                self.light(&ty, &params);
+            }
+            "Shape" => {
+                let (ty, params) = named()?;
+                if self.in_object {
+                    self.warn("object instancing is not supported".to_string());
+                } else {
+                    self.shape(&ty, &params, directory)?;
+                }
+            }
+            "ObjectBegin" => {
+                self.warn("object instancing is not supported".to_string());
+                self.in_object = true;
+            }
+            "ObjectEnd" => self.in_object = false,
+            "Include" => {
+                let (file, _) = named()?;
+                let path = directory.join(&file);
+                let canonical = fs::canonicalize(&path)?;
+                if self.includes.contains(&canonical) {
+                    self.warn(format!("Include \"{}\" is recursive; skipped", file));
+                } else {
+                    let text = fs::read_to_string(&path)?;
+                    let directory = path.parent().unwrap_or(directory).to_path_buf();
+                    self.includes.push(canonical);
+                    let result = self.parse(&text, &directory);
+                    self.includes.pop();
+                    result?;
+                }
+            }
+            _ => self.warn(format!("{} is not supported", directive)),
+        }
+        Ok(())
+    }
+    // Directives apply their transform before the current one, like pbrt's post-multiply.
+    fn concat(&mut self, t: Transform) {
+        self.state.ctm = t.then(&self.state.ctm);
+    }
+    fn world_begin(&mut self) {
+        let (world_to_camera, ty, params) = self.camera.clone();
+        let camera_to_world = world_to_camera.inverse();
+        let lookfrom = camera_to_world.transform_point(&Point::default());
+        let forward = camera_to_world.transform_direction(&Vec3::new(0.0, 0.0, 1.0));
+        let up = camera_to_world.transform_direction(&Vec3::new(0.0, 1.0, 0.0));
+        let right = camera_to_world
+            .transform_direction(&Vec3::new(1.0, 0.0, 0.0))
+            .unit();
+        let aspect_ratio = self.film.0 as f64 / self.film.1 as f64;
+        let projection = match ty.as_str() {
+            "perspective" => Projection::Perspective,
+            // pbrt's default screen window spans [-1, 1] along the shorter image axis.
+            "orthographic" => Projection::Orthographic {
+                height: match params.floats("screenwindow") {
+                    Some(&[_, _, y0, y1]) => y1 - y0,
+                    _ => 2.0 / aspect_ratio.min(1.0),
+                },
+            },
+            "environment" => Projection::Equirectangular,
+            _ => {
+                self.warn(format!(
+                    "Camera \"{}\" is not supported; using perspective",
+                    ty
+                ));
+                Projection::Perspective
+            }
+        };
+        // pbrt's fov spans the shorter image axis.
+        let fov = params.float("fov", 90.0);
+        let vfov = if aspect_ratio >= 1.0 {
+            fov
+        } else {
+            2.0 * ((fov.to_radians() / 2.0).tan() / aspect_ratio)
+                .atan()
+                .to_degrees()
+        };
+        // The focus distance scales camera rays, so only honour pbrt's huge default with a lens.
+        let aperture = 2.0 * params.float("lensradius", 0.0);
+        let focus_distance = if aperture > 0.0 {
+            params.float("focaldistance", 1e6)
+        } else {
+            1.0
+        };
+        self.scene.cameras.push(CameraSettings {
+            lookfrom,
+            lookat: lookfrom + forward.unit(),
+            vup: up,
+            vfov,
+            aspect_ratio,
+            aperture,
+            focus_distance,
+            projection,
+            time0: params.float("shutteropen", 0.0),
+            time1: params.float("shutterclose", 1.0),
+        });
+        self.scene.resolution = Some(self.film);
+
+        let offset = 2.0 * lookfrom.dot(&right);
+        let mut m = [[0.0, 0.0, 0.0, 1.0]; 4];
+        for (i, row) in m.iter_mut().take(3).enumerate() {
+            for (j, x) in row.iter_mut().take(3).enumerate() {
+                *x = if i == j { 1.0 } else { 0.0 } - 2.0 * right[i] * right[j];
+            }
+            row[3] = offset * right[i];
+        }
+        self.mirror = Transform::new(Matrix4::new(m)).unwrap_or_default();
+
+        self.state.ctm = Transform::identity();
+        self.coordinate_systems
+            .insert("world".to_string(), Transform::identity());
+        self.in_world = true;
+    }
+    fn color(&mut self, params: &Params, name: &str, default: Color) -> Color {
+        let param = match params.get(name) {
+            Some(param) => param,
+            None => return default,
+        };
+        let n = &param.numbers;
+        match (param.ty.as_str(), n.len()) {
+            ("rgb", 3) | ("color", 3) => Color::new(n[0], n[1], n[2]),
+            ("blackbody", 1) | ("blackbody", 2) => {
+                Spd::new_blackbody(n[0]).to_rgb() * n.get(1).copied().unwrap_or(1.0)
+            }
+            ("float", 1) => Color::new(n[0], n[0], n[0]),
+            (ty, _) => {
+                self.warn(format!("\"{} {}\" is not supported", ty, name));
+                default
+            }
+        }
+    }
+    // pbrt maps its roughness parameter to a GGX alpha; this crate squares roughness instead.
+    fn roughness(params: &Params, default: f64) -> f64 {
+        let roughness = params.float("roughness", default);
+        let roughness = match (params.floats("uroughness"), params.floats("vroughness")) {
+            (Some(&[u, ..]), Some(&[v, ..])) => 0.5 * (u + v),
+            _ => roughness,
+        };
+        let alpha = if params.string("remaproughness") == Some("false") {
+            roughness
+        } else {
+            let x = roughness.max(1e-3).ln();
+            1.62142
+                + 0.819955 * x
+                + 0.1734 * x * x
+                + 0.0171201 * x.powi(3)
+                + 0.000640711 * x.powi(4)
+        };
+        alpha.max(0.0).sqrt()
+    }
+    fn material(&mut self, ty: &str, params: &Params) -> Rc<dyn Material> {
+        let check_textures = |parser: &mut Self| {
+            if params.0.iter().any(|p| p.ty == "texture") {
+                parser.warn("textures are not supported".to_string());
+            }
+        };
+        check_textures(self);
+        match ty {
+            "matte" => {
+                let kd = self.color(params, "Kd", Color::new(0.5, 0.5, 0.5));
+                Rc::new(Lambertian::new(kd))
+            }
+            "plastic" => {
+                let kd = self.color(params, "Kd", Color::new(0.25, 0.25, 0.25));
+                Rc::new(Coated::new(
+                    Box::new(Lambertian::new(kd)),
+                    1.5,
+                    Self::roughness(params, 0.1),
+                    Color::new(1.0, 1.0, 1.0),
+                ))
+            }
+            "metal" => {
+                // Copper, pbrt's default.
+                let eta = self.color(params, "eta", Color::new(0.200, 0.924, 1.102));
+                let k = self.color(params, "k", Color::new(3.912, 2.452, 2.142));
+                let mut f0 = Color::default();
+                for a in 0..3 {
+                    f0[a] = ((eta[a] - 1.0).powi(2) + k[a] * k[a])
+                        / ((eta[a] + 1.0).powi(2) + k[a] * k[a]);
+                }
+                Rc::new(
+                    Principled::new(Rc::new(SolidColor::new(f0)))
+                        .with_metallic(Rc::new(SolidColor::new_scalar(1.0)))
+                        .with_roughness(Rc::new(SolidColor::new_scalar(Self::roughness(
+                            params, 0.01,
+                        )))),
+                )
+            }
+            "glass" => {
+                let eta = params.float("eta", params.float("index", 1.5));
+                Rc::new(Dielectric::new(eta))
+            }
+            _ => {
+                self.warn(format!("Material \"{}\" is not supported; using matte", ty));
+                Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+            }
+        }
+    }
+    fn light(&mut self, ty: &str, params: &Params) {
+        let to_world = self.state.ctm.then(&self.mirror);
+        let scale = self.color(params, "scale", Color::new(1.0, 1.0, 1.0));
+        let white = Color::new(1.0, 1.0, 1.0);
+        let light = match ty {
+            "point" => Light::Point {
+                position: to_world.transform_point(&params.point("from", Point::default())),
+                intensity: self.color(params, "I", white) * scale,
+            },
+            "spot" => {
+                let from = params.point("from", Point::default());
+                let to = params.point("to", Point::new(0.0, 0.0, 1.0));
+                let cone = params.float("coneangle", 30.0);
+                let delta = params.float("conedelta", 5.0);
+                Light::Spot {
+                    position: to_world.transform_point(&from),
+                    direction: to_world.transform_direction(&(to - from)).unit(),
+                    intensity: self.color(params, "I", white) * scale,
+                    inner_angle: (cone - delta).max(0.0).to_radians(),
+                    outer_angle: cone.to_radians(),
+                }
+            }
+            "distant" => {
+                let from = params.point("from", Point::default());
+                let to = params.point("to", Point::new(0.0, 0.0, 1.0));
+                Light::Distant {
+                    direction: to_world.transform_direction(&(from - to)).unit(),
+                    irradiance: self.color(params, "L", white) * scale,
+                }
+            }
+            "infinite" => {
+                if params.get("mapname").is_some() {
+                    self.warn("environment maps are not supported".to_string());
+                }
+                Light::Infinite {
+                    radiance: self.color(params, "L", white) * scale,
+                }
+            }
+            _ => {
+                self.warn(format!("LightSource \"{}\" is not supported", ty));
+                return;
+            }
+        };
+        self.scene.lights.push(light);
+    }
+    fn shape(&mut self, ty: &str, params: &Params, directory: &Path) -> io::Result<()> {
+        let to_world = self.state.ctm.then(&self.mirror);
+        let material: Rc<dyn Material> = match self.state.area_light {
+            Some(radiance) => Rc::new(DiffuseLight::new(Rc::new(SolidColor::new(radiance)))),
+            None => self.state.material.clone(),
+        };
+        let mut mesh = match ty {
+            "sphere" => {
+                if ["zmin", "zmax", "phimax"]
+                    .iter()
+                    .any(|p| params.get(p).is_some())
+                {
+                    self.warn("partial spheres are rendered whole".to_string());
+                }
+                let radius = params.float("radius", 1.0);
+                let sphere = Sphere::new(Point::default(), radius, material);
+                self.scene
+                    .objects
+                    .push(Box::new(Transformed::new(sphere, to_world)));
+                return Ok(());
+            }
+            "trianglemesh" => {
+                let p = params.floats("P").unwrap_or(&[]);
+                let positions: Vec<Point> = p
+                    .chunks_exact(3)
+                    .map(|p| Point::new(p[0], p[1], p[2]))
+                    .collect();
+                let indices = match params.floats("indices") {
+                    Some(indices) => indices
+                        .chunks_exact(3)
+                        .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+                        .collect(),
+                    None if positions.len() == 3 => vec![[0, 1, 2]],
+                    None => Vec::new(),
+                };
+                let uv = params
+                    .floats("uv")
+                    .or_else(|| params.floats("st"))
+                    .unwrap_or(&[]);
+                TriangleMesh {
+                    positions,
+                    normals: params
+                        .floats("N")
+                        .unwrap_or(&[])
+                        .chunks_exact(3)
+                        .map(|n| Vec3::new(n[0], n[1], n[2]))
+                        .collect(),
+                    colors: Vec::new(),
+                    uvs: uv.chunks_exact(2).map(|t| (t[0], t[1])).collect(),
+                    indices,
+                }
+            }
+            "plymesh" => {
+                let file = params
+                    .string("filename")
+                    .ok_or_else(|| invalid("plymesh without a filename".to_string()))?;
+                let path: PathBuf = directory.join(file);
+                TriangleMesh::load_ply(path)?
+            }
+            _ => {
+                self.warn(format!("Shape \"{}\" is not supported", ty));
+                return Ok(());
+            }
+        };
+        mesh.transform(&to_world);
+        self.scene.objects.push(Box::new(Mesh::new(mesh, material)));
+        Ok(())
+    }
+}
+
+#[test]
+fn test_read_pbrt() {
+    let text = r#"
+        # A camera at the origin looking down +z, with one sphere to its right.
+        LookAt 0 0 0  0 0 1  0 1 0
+        Camera "perspective" "float fov" [ 40 ] "float shutterclose" 0.5
+        Film "image" "integer xresolution" [ 200 ] "integer yresolution" 100
+        Sampler "halton"
+        WorldBegin
+        LightSource "point" "rgb I" [ 1 2 3 ] "point from" [ 0 5 0 ]
+        AttributeBegin
+          Material "glass"
+          Translate 2 0 10
+          Shape "sphere" "float radius" 0.5
+        AttributeEnd
+        AttributeBegin
+          AreaLightSource "diffuse" "blackbody L" [ 6500 2 ]
+          Shape "trianglemesh" "integer indices" [ 0 1 2 ] "point P" [ -1 2 5  1 2 5  0 3 5 ]
+        AttributeEnd
+        Scale 1 0 1
+        Shape "disk"
+        WorldEnd
+    "#;
+    let scene = Scene::read_pbrt(text, Path::new(".")).unwrap();
+    assert_eq!(scene.resolution, Some((200, 100)));
+    assert_eq!(scene.objects.len(), 2);
+    assert_eq!(
+        scene.warnings,
+        vec![
+            "Sampler is not supported".to_string(),
+            "degenerate Scale is ignored".to_string(),
+            "Shape \"disk\" is not supported".to_string()
+        ]
+    );
+    let camera = &scene.cameras[0];
+    assert!((camera.vfov - 40.0).abs() < 1e-12);
+    assert!((camera.aspect_ratio - 2.0).abs() < 1e-12);
+    assert_eq!((camera.time0, camera.time1), (0.0, 0.5));
+    // pbrt puts camera-space +x on the right of the image; so must the imported scene.
+    let forward = camera.lookat - camera.lookfrom;
+    let right = forward.cross(&camera.vup);
+    let mut bounds = crate::aabb::Aabb::default();
+    assert!(scene.objects[0].bounding_box(0.0, 0.0, &mut bounds));
+    let center = (bounds.min() + bounds.max()) * 0.5;
+    assert!((center - camera.lookfrom).dot(&right) > 1.0);
+    assert!(((center - camera.lookfrom).dot(&forward) - 10.0).abs() < 1e-6);
+    match &scene.lights[0] {
+        Light::Point { intensity, .. } => {
+            assert!((*intensity - Color::new(1.0, 2.0, 3.0)).length() < 1e-12)
+        }
+        light => panic!("unexpected light {:?}", light),
+    }
+}
+
+#[test]
+fn test_pbrt_include_cycle() {
+    let directory = std::env::temp_dir().join(format!("pbrt-include-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let files = [
+        ("scene.pbrt", "WorldBegin\nInclude \"a.pbrt\"\nWorldEnd\n"),
+        ("a.pbrt", "Shape \"sphere\"\nInclude \"b.pbrt\"\n"),
+        // Spelled differently, but the same file.
+        ("b.pbrt", "Include \"./a.pbrt\"\n"),
+    ];
+    for (name, text) in files.iter() {
+        fs::write(directory.join(name), text).unwrap();
+    }
+    let scene = Scene::load_pbrt(directory.join("scene.pbrt"));
+    fs::remove_dir_all(&directory).unwrap();
+    let scene = scene.unwrap();
+    assert_eq!(scene.objects.len(), 1);
+    assert_eq!(
+        scene.warnings,
+        vec!["Include \"./a.pbrt\" is recursive; skipped".to_string()]
+    );
+}
@@ -0,0 +1,89 @@
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    onb::Onb,
+    ray::Ray,
+    vec3::{Point, Vec3},
+};
+use std::rc::Rc;
+
+// An infinite plane through `point`. Texture coordinates repeat every unit along the plane.
+#[derive(Clone)]
+pub struct Plane {
+    point: Point,
+    frame: Onb,
+    material: Rc<dyn Material>,
+}
+
+impl Plane {
+    pub fn new(point: Point, normal: Vec3, material: Rc<dyn Material>) -> Self {
+        Self {
+            point,
+            frame: Onb::new_from_w(&normal),
+            material,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let normal = self.frame.w();
+        let denom = normal.dot(&r.direction);
+        if denom.abs() < 1e-8 {
+            return false;
+        }
+        let t = (self.point - r.origin).dot(&normal) / denom;
+        if t < t_min || t_max < t {
+            return false;
+        }
+
+        rec.t = t;
+        rec.point = r.at(t);
+        let local = self.frame.to_local(&(rec.point - self.point));
+        rec.u = local[0].rem_euclid(1.0);
+        rec.v = local[1].rem_euclid(1.0);
+        rec.set_face_normal(r, &normal);
+        rec.material = self.material.clone();
+        true
+    }
+    fn bounding_box(&self, _time0: f64, _time1: f64, _output_box: &mut Aabb) -> bool {
+        false
+    }
+}
+
+#[test]
+fn test_plane() {
+    use crate::{material::Lambertian, vec3::Color};
+
+    let material = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    let plane = Plane::new(Point::default(), up, material);
+    let ray = |origin: Point, direction: Vec3| Ray {
+        origin,
+        direction,
+        ..Ray::default()
+    };
+    let mut rec = HitRecord::default();
+    assert!(plane.hit(
+        &ray(Point::new(0.25, 5.0, 0.5), -up),
+        0.001,
+        f64::INFINITY,
+        &mut rec
+    ));
+    assert!((rec.t - 5.0).abs() < 1e-12 && rec.front_face);
+    assert!((rec.normal - up).length() < 1e-12);
+    // The frame around +y has u along -x and v along -z, and the coordinates wrap.
+    assert!((rec.u - 0.75).abs() < 1e-12 && (rec.v - 0.5).abs() < 1e-12);
+    assert!(plane.hit(
+        &ray(Point::new(0.0, -2.0, 0.0), up),
+        0.001,
+        f64::INFINITY,
+        &mut rec
+    ));
+    assert!((rec.t - 2.0).abs() < 1e-12 && !rec.front_face);
+    assert!((rec.normal + up).length() < 1e-12);
+    let parallel = ray(Point::new(0.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    assert!(!plane.hit(&parallel, 0.001, f64::INFINITY, &mut rec));
+    assert!(!plane.bounding_box(0.0, 0.0, &mut Aabb::default()));
+}
@@ -0,0 +1,387 @@
+use crate::{
+    mesh::TriangleMesh,
+    vec3::{Color, Point, Vec3},
+};
+use std::{
+    fs::File,
+    io::{self, BufReader, Read},
+    path::Path,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => return None,
+        })
+    }
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+    // Scale that maps the full integer range to [0, 1], used for colors.
+    fn normalizer(self) -> f64 {
+        match self {
+            Self::U8 => 1.0 / 255.0,
+            Self::U16 => 1.0 / 65535.0,
+            _ => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Property {
+    name: String,
+    // Some(count type) for list properties.
+    list: Option<Scalar>,
+    scalar: Scalar,
+    values: Vec<f64>,
+    lists: Vec<Vec<f64>>,
+}
+
+#[derive(Debug, Clone)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+// The parsed contents of a PLY file, stored per property.
+#[derive(Debug, Clone)]
+pub(crate) struct Ply {
+    elements: Vec<Element>,
+}
+
+enum Source<'a> {
+    Ascii(std::str::SplitWhitespace<'a>),
+    Binary {
+        data: &'a [u8],
+        position: usize,
+        big_endian: bool,
+    },
+}
+
+impl<'a> Source<'a> {
+    fn read(&mut self, scalar: Scalar) -> io::Result<f64> {
+        match self {
+            Source::Ascii(tokens) => tokens
+                .next()
+                .and_then(|token| token.parse::<f64>().ok())
+                .ok_or_else(|| invalid("truncated or malformed PLY data")),
+            Source::Binary {
+                data,
+                position,
+                big_endian,
+            } => {
+                let size = scalar.size();
+                if *position + size > data.len() {
+                    return Err(invalid("truncated PLY data"));
+                }
+                let mut bytes = [0u8; 8];
+                bytes[..size].copy_from_slice(&data[*position..*position + size]);
+                if *big_endian {
+                    bytes[..size].reverse();
+                }
+                *position += size;
+                let b2 = [bytes[0], bytes[1]];
+                let b4 = [bytes[0], bytes[1], bytes[2], bytes[3]];
+                Ok(match scalar {
+                    Scalar::I8 => f64::from(bytes[0] as i8),
+                    Scalar::U8 => f64::from(bytes[0]),
+                    Scalar::I16 => f64::from(i16::from_le_bytes(b2)),
+                    Scalar::U16 => f64::from(u16::from_le_bytes(b2)),
+                    Scalar::I32 => f64::from(i32::from_le_bytes(b4)),
+                    Scalar::U32 => f64::from(u32::from_le_bytes(b4)),
+                    Scalar::F32 => f64::from(f32::from_le_bytes(b4)),
+                    Scalar::F64 => f64::from_le_bytes(bytes),
+                })
+            }
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+impl Ply {
+    pub(crate) fn read(reader: &mut dyn Read) -> io::Result<Self> {
+        let mut format = None;
+        let mut elements: Vec<Element> = Vec::new();
+        let mut first = true;
+        loop {
+            let line = read_line(reader)?;
+            let mut words = line.split_whitespace();
+            let keyword = words.next().unwrap_or("");
+            if first {
+                if keyword != "ply" {
+                    return Err(invalid("not a PLY file"));
+                }
+                first = false;
+                continue;
+            }
+            match keyword {
+                "format" => {
+                    format = Some(match words.next() {
+                        Some("ascii") => Format::Ascii,
+                        Some("binary_little_endian") => Format::BinaryLittleEndian,
+                        Some("binary_big_endian") => Format::BinaryBigEndian,
+                        _ => return Err(invalid("unknown PLY format")),
+                    })
+                }
+                "element" => {
+                    let name = words.next().ok_or_else(|| invalid("unnamed PLY element"))?;
+                    let count = words
+                        .next()
+                        .and_then(|c| c.parse().ok())
+                        .ok_or_else(|| invalid("bad PLY element count"))?;
+                    elements.push(Element {
+                        name: name.to_string(),
+                        count,
+                        properties: Vec::new(),
+                    });
+                }
+                "property" => {
+                    let words: Vec<&str> = words.collect();
+                    let (list, scalar, name) = match words.as_slice() {
+                        ["list", count, item, name] => {
+                            (Scalar::parse(count), Scalar::parse(item), name)
+                        }
+                        [scalar, name] => (None, Scalar::parse(scalar), name),
+                        _ => return Err(invalid("malformed PLY property")),
+                    };
+                    let scalar = scalar.ok_or_else(|| invalid("unknown PLY property type"))?;
+                    if words[0] == "list" && list.is_none() {
+                        return Err(invalid("unknown PLY list count type"));
+                    }
+                    elements
+                        .last_mut()
+                        .ok_or_else(|| invalid("PLY property outside an element"))?
+                        .properties
+                        .push(Property {
+                            name: name.to_string(),
+                            list,
+                            scalar,
+                            values: Vec::new(),
+                            lists: Vec::new(),
+                        });
+                }
+                "end_header" => break,
+                _ => {}
+            }
+        }
+        let format = format.ok_or_else(|| invalid("missing PLY format"))?;
+
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let text;
+        let mut source = match format {
+            Format::Ascii => {
+                text = String::from_utf8_lossy(&data);
+                Source::Ascii(text.split_whitespace())
+            }
+            _ => Source::Binary {
+                data: &data,
+                position: 0,
+                big_endian: format == Format::BinaryBigEndian,
+            },
+        };
+        for element in elements.iter_mut() {
+            for _ in 0..element.count {
+                for property in element.properties.iter_mut() {
+                    match property.list {
+                        Some(count_type) => {
+                            let count = source.read(count_type)? as usize;
+                            let list = (0..count)
+                                .map(|_| source.read(property.scalar))
+                                .collect::<io::Result<Vec<_>>>()?;
+                            property.lists.push(list);
+                        }
+                        None => property.values.push(source.read(property.scalar)?),
+                    }
+                }
+            }
+        }
+        Ok(Self { elements })
+    }
+    fn property(&self, element: &str, names: &[&str]) -> Option<&Property> {
+        let element = self.elements.iter().find(|e| e.name == element)?;
+        names
+            .iter()
+            .find_map(|name| element.properties.iter().find(|p| p.name == *name))
+    }
+    fn columns(&self, element: &str, names: [&[&str]; 3]) -> Option<[&Property; 3]> {
+        Some([
+            self.property(element, names[0])?,
+            self.property(element, names[1])?,
+            self.property(element, names[2])?,
+        ])
+    }
+    pub(crate) fn positions(&self) -> Vec<Point> {
+        self.vectors(["x"].as_ref(), ["y"].as_ref(), ["z"].as_ref())
+    }
+    pub(crate) fn normals(&self) -> Vec<Vec3> {
+        self.vectors(["nx"].as_ref(), ["ny"].as_ref(), ["nz"].as_ref())
+    }
+    // Integer colors are scaled to [0, 1]; float colors are used as they are.
+    pub(crate) fn colors(&self) -> Vec<Color> {
+        match self.columns(
+            "vertex",
+            [
+                &["red", "r", "diffuse_red"],
+                &["green", "g", "diffuse_green"],
+                &["blue", "b", "diffuse_blue"],
+            ],
+        ) {
+            Some([r, g, b]) => (0..r.values.len())
+                .map(|i| {
+                    Color::new(
+                        r.values[i] * r.scalar.normalizer(),
+                        g.values[i] * g.scalar.normalizer(),
+                        b.values[i] * b.scalar.normalizer(),
+                    )
+                })
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+    pub(crate) fn uvs(&self) -> Vec<(f64, f64)> {
+        let u = self.property("vertex", &["u", "s", "texture_u", "texture_s"]);
+        let v = self.property("vertex", &["v", "t", "texture_v", "texture_t"]);
+        match (u, v) {
+            (Some(u), Some(v)) => u
+                .values
+                .iter()
+                .copied()
+                .zip(v.values.iter().copied())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+    // Polygons are split into triangle fans.
+    pub(crate) fn triangles(&self) -> Vec<[usize; 3]> {
+        let faces = match self.property("face", &["vertex_indices", "vertex_index"]) {
+            Some(faces) => faces,
+            None => return Vec::new(),
+        };
+        let mut triangles = Vec::new();
+        for face in faces.lists.iter() {
+            for i in 2..face.len() {
+                triangles.push([face[0] as usize, face[i - 1] as usize, face[i] as usize]);
+            }
+        }
+        triangles
+    }
+    fn vectors(&self, x: &[&str], y: &[&str], z: &[&str]) -> Vec<Vec3> {
+        match self.columns("vertex", [x, y, z]) {
+            Some([x, y, z]) => (0..x.values.len())
+                .map(|i| Vec3::new(x.values[i], y.values[i], z.values[i]))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+fn read_line(reader: &mut dyn Read) -> io::Result<String> {
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    loop {
+        if reader.read(&mut byte)? == 0 {
+            return Err(invalid("unexpected end of PLY header"));
+        }
+        if byte[0] == b'\n' {
+            break;
+        }
+        line.push(byte[0]);
+    }
+    Ok(String::from_utf8_lossy(&line).trim().to_string())
+}
+
+impl TriangleMesh {
+    pub fn load_ply<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::read_ply(&mut BufReader::new(File::open(path)?))
+    }
+    // Reads ASCII or binary PLY with optional vertex normals, colors and texture coordinates.
+    pub fn read_ply(reader: &mut dyn Read) -> io::Result<Self> {
+        let ply = Ply::read(reader)?;
+        let mut mesh = Self {
+            positions: ply.positions(),
+            normals: ply.normals(),
+            colors: ply.colors(),
+            uvs: ply.uvs(),
+            indices: ply.triangles(),
+        };
+        if mesh.positions.is_empty() {
+            return Err(invalid("PLY file has no vertex positions"));
+        }
+        mesh.validate();
+        Ok(mesh)
+    }
+}
+
+#[test]
+fn test_read_ply() {
+    let ascii = b"ply\nformat ascii 1.0\ncomment test\nelement vertex 4\nproperty float x\n\
+property float y\nproperty float z\nproperty uchar red\nproperty uchar green\n\
+property uchar blue\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n\
+0 0 0 255 0 0\n1 0 0 0 255 0\n1 1 0 0 0 255\n0 1 0 255 255 255\n4 0 1 2 3\n";
+    let mesh = TriangleMesh::read_ply(&mut &ascii[..]).unwrap();
+    assert_eq!(mesh.positions.len(), 4);
+    assert_eq!(mesh.indices, vec![[0, 1, 2], [0, 2, 3]]);
+    assert!((mesh.colors[1] - Color::new(0.0, 1.0, 0.0)).length() < 1e-12);
+    assert!(mesh.normals.is_empty() && mesh.uvs.is_empty());
+
+    let mut binary = b"ply\nformat binary_big_endian 1.0\nelement vertex 3\nproperty double x\n\
+property double y\nproperty double z\nproperty float nx\nproperty float ny\nproperty float nz\n\
+element face 1\nproperty list uchar uint vertex_indices\nend_header\n"
+        .to_vec();
+    for p in [[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 2.0, 0.0]].iter() {
+        for x in p.iter() {
+            binary.extend_from_slice(&f64::to_be_bytes(*x));
+        }
+        for n in [0.0f32, 0.0, 1.0].iter() {
+            binary.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+    binary.push(3);
+    for i in 0..3u32 {
+        binary.extend_from_slice(&i.to_be_bytes());
+    }
+    let mesh = TriangleMesh::read_ply(&mut &binary[..]).unwrap();
+    assert!((mesh.positions[1] - Point::new(2.0, 0.0, 0.0)).length() < 1e-12);
+    assert!((mesh.normals[2] - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-12);
+    assert_eq!(mesh.indices, vec![[0, 1, 2]]);
+}
@@ -0,0 +1,295 @@
+use crate::{
+    aabb::Aabb,
+    bvh::Bvh,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    ply::Ply,
+    ray::Ray,
+    sphere::Sphere,
+    vec3::{Color, Point, Vec3},
+};
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+    rc::Rc,
+};
+
+// Points with optional per-point attributes. Each attribute is either empty or has one
+// entry per position.
+#[derive(Debug, Clone, Default)]
+pub struct PointSet {
+    pub positions: Vec<Point>,
+    pub normals: Vec<Vec3>,
+    pub colors: Vec<Color>,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+impl PointSet {
+    pub fn bounds(&self) -> Aabb {
+        Aabb::from_points(&self.positions)
+    }
+    // Drops attributes whose length does not match the positions.
+    pub fn validate(&mut self) {
+        let n = self.positions.len();
+        if self.normals.len() != n {
+            self.normals.clear();
+        }
+        if self.colors.len() != n {
+            self.colors.clear();
+        }
+    }
+    pub fn load_ply<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::read_ply(&mut BufReader::new(File::open(path)?))
+    }
+    // Reads the vertices of an ASCII or binary PLY, with optional normals and colors.
+    pub fn read_ply(reader: &mut dyn Read) -> io::Result<Self> {
+        let ply = Ply::read(reader)?;
+        let mut points = Self {
+            positions: ply.positions(),
+            normals: ply.normals(),
+            colors: ply.colors(),
+        };
+        if points.positions.is_empty() {
+            return Err(invalid("PLY file has no vertex positions"));
+        }
+        points.validate();
+        Ok(points)
+    }
+    pub fn load_xyz<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::read_xyz(&mut BufReader::new(File::open(path)?))
+    }
+    // Reads one point per line as `x y z`, `x y z r g b` or `x y z r g b nx ny nz`. Colors
+    // are scaled from [0, 255] unless every one of them is within [0, 1]. Blank lines and
+    // lines starting with `#` are skipped.
+    pub fn read_xyz(reader: &mut dyn BufRead) -> io::Result<Self> {
+        let mut points = Self::default();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let values = line
+                .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+                .filter(|s| !s.is_empty())
+                .map(|s| s.parse::<f64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid("malformed XYZ point"))?;
+            if values.len() < 3 {
+                return Err(invalid("XYZ point has fewer than three coordinates"));
+            }
+            let vector = |i: usize| Vec3::new(values[i], values[i + 1], values[i + 2]);
+            points.positions.push(vector(0));
+            if values.len() >= 6 {
+                points.colors.push(vector(3));
+            }
+            if values.len() >= 9 {
+                points.normals.push(vector(6));
+            }
+        }
+        if points.positions.is_empty() {
+            return Err(invalid("XYZ file has no points"));
+        }
+        if points
+            .colors
+            .iter()
+            .any(|c| (0..3).any(|i| !(0.0..=1.0).contains(&c[i])))
+        {
+            for c in points.colors.iter_mut() {
+                *c /= 255.0;
+            }
+        }
+        points.validate();
+        Ok(points)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplatShape {
+    // Disks facing along the point normals, or towards each ray when there are none.
+    Disk,
+    Sphere,
+}
+
+#[derive(Clone)]
+struct Splat {
+    points: Rc<PointSet>,
+    index: usize,
+    radius: f64,
+    shape: SplatShape,
+}
+
+impl Splat {
+    fn normal(&self) -> Option<Vec3> {
+        self.points.normals.get(self.index).copied()
+    }
+}
+
+impl Hittable for Splat {
+    // Fills in everything but the material, which the cloud sets once for the nearest hit.
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let center = self.points.positions[self.index];
+        let oc = r.origin - center;
+        let (t, outward_normal) = match (self.shape, self.normal()) {
+            (SplatShape::Disk, normal) => {
+                let normal = normal.unwrap_or_else(|| -r.direction.unit());
+                let denominator = r.direction.dot(&normal);
+                if denominator.abs() < 1e-12 {
+                    return false;
+                }
+                let t = -oc.dot(&normal) / denominator;
+                if t < t_min || t_max < t {
+                    return false;
+                }
+                if (r.at(t) - center).length_squared() > self.radius * self.radius {
+                    return false;
+                }
+                (t, normal)
+            }
+            (SplatShape::Sphere, _) => {
+                let a = r.direction.length_squared();
+                let half_b = oc.dot(&r.direction);
+                let c = oc.length_squared() - self.radius * self.radius;
+                let discriminant = half_b * half_b - a * c;
+                if discriminant < 0.0 {
+                    return false;
+                }
+                let sqrtd = discriminant.sqrt();
+                let mut root = (-half_b - sqrtd) / a;
+                if root < t_min || t_max < root {
+                    root = (-half_b + sqrtd) / a;
+                    if root < t_min || t_max < root {
+                        return false;
+                    }
+                }
+                (root, (r.at(root) - center) / self.radius)
+            }
+        };
+        rec.t = t;
+        rec.point = r.at(t);
+        rec.set_face_normal(r, &outward_normal);
+        // Points have no surface parameterization of their own; use the sphere mapping of the
+        // direction from the center so textures still vary over a splat.
+        let (u, v) = Sphere::uv(&(rec.point - center).unit());
+        rec.u = u;
+        rec.v = v;
+        rec.vertex_color = self.points.colors.get(self.index).copied();
+        rec.tangent = None;
+        true
+    }
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        let center = self.points.positions[self.index];
+        let extent = match (self.shape, self.normal()) {
+            // A disk spans radius * sin(angle between its normal and the axis) along each axis.
+            (SplatShape::Disk, Some(n)) => {
+                let n = n.unit();
+                let e = |i: usize| self.radius * (1.0 - n[i] * n[i]).max(0.0).sqrt() + 1e-4;
+                Vec3::new(e(0), e(1), e(2))
+            }
+            _ => Vec3::new(self.radius, self.radius, self.radius),
+        };
+        *output_box = Aabb::new(center - extent, center + extent);
+        true
+    }
+}
+
+// Points rendered as disks or small spheres of one radius and material, accelerated by their
+// own BVH. Per-point colors reach the material through `texture::VertexColor`.
+#[derive(Clone)]
+pub struct PointCloud {
+    bvh: Bvh<Splat>,
+    material: Rc<dyn Material>,
+}
+
+impl PointCloud {
+    pub fn new(
+        mut points: PointSet,
+        radius: f64,
+        shape: SplatShape,
+        material: Rc<dyn Material>,
+    ) -> Self {
+        points.validate();
+        for n in points.normals.iter_mut() {
+            if !n.is_near_zero() {
+                *n = n.unit();
+            }
+        }
+        let points = Rc::new(points);
+        let splats = (0..points.positions.len())
+            .filter(|&index| {
+                // A zero normal cannot orient a disk.
+                shape == SplatShape::Sphere
+                    || points.normals.is_empty()
+                    || !points.normals[index].is_near_zero()
+            })
+            .map(|index| Splat {
+                points: points.clone(),
+                index,
+                radius,
+                shape,
+            })
+            .collect();
+        Self {
+            bvh: Bvh::new(splats, 0.0, 0.0),
+            material,
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.bvh.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.bvh.is_empty()
+    }
+}
+
+impl Hittable for PointCloud {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        if !self.bvh.hit(r, t_min, t_max, rec) {
+            return false;
+        }
+        rec.material = self.material.clone();
+        true
+    }
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        self.bvh.bounding_box(time0, time1, output_box)
+    }
+}
+
+#[test]
+fn test_point_cloud() {
+    use crate::material::Lambertian;
+
+    let xyz = b"# two points\n0 0 0 255 0 0 0 0 1\n\n2,0,0,0,255,0,0,0,1\n";
+    let points = PointSet::read_xyz(&mut &xyz[..]).unwrap();
+    assert_eq!(points.positions.len(), 2);
+    assert!((points.colors[1] - Color::new(0.0, 1.0, 0.0)).length() < 1e-12);
+    assert!(PointSet::read_xyz(&mut &b"1 2\n"[..]).is_err());
+
+    let material = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let ray = |x: f64, y: f64| Ray {
+        origin: Point::new(x, y, 5.0),
+        direction: Vec3::new(0.0, 0.0, -1.0),
+        ..Default::default()
+    };
+    let mut rec = HitRecord::default();
+    let disks = PointCloud::new(points.clone(), 0.5, SplatShape::Disk, material.clone());
+    assert!(disks.hit(&ray(2.3, 0.3), 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 5.0).abs() < 1e-9 && rec.front_face);
+    assert!((rec.vertex_color.unwrap() - Color::new(0.0, 1.0, 0.0)).length() < 1e-12);
+    assert!(!disks.hit(&ray(2.4, 0.4), 0.001, f64::INFINITY, &mut rec));
+
+    let spheres = PointCloud::new(points, 0.5, SplatShape::Sphere, material);
+    assert!(spheres.hit(&ray(0.0, 0.3), 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - (5.0 - 0.4)).abs() < 1e-9);
+    assert!(!spheres.hit(&ray(1.0, 0.0), 0.001, f64::INFINITY, &mut rec));
+}
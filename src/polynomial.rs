@@ -0,0 +1,183 @@
+// Real roots of low degree polynomials, after Schwarze, "Cubic and Quartic Roots" (Graphics Gems).
+// Coefficients are given in increasing order of degree: c[0] + c[1] x + c[2] x² + ...
+
+const EPSILON: f64 = 1e-9;
+
+fn is_zero(x: f64) -> bool {
+    x.abs() < EPSILON
+}
+
+pub fn solve_quadratic(c: [f64; 3]) -> Vec<f64> {
+    if is_zero(c[2]) {
+        return if is_zero(c[1]) {
+            Vec::new()
+        } else {
+            vec![-c[0] / c[1]]
+        };
+    }
+    // normal form: x² + px + q = 0
+    let p = c[1] / (2.0 * c[2]);
+    let q = c[0] / c[2];
+    let d = p * p - q;
+    if is_zero(d) {
+        vec![-p]
+    } else if d < 0.0 {
+        Vec::new()
+    } else {
+        let sqrt_d = d.sqrt();
+        vec![sqrt_d - p, -sqrt_d - p]
+    }
+}
+
+pub fn solve_cubic(c: [f64; 4]) -> Vec<f64> {
+    if is_zero(c[3]) {
+        return solve_quadratic([c[0], c[1], c[2]]);
+    }
+    // normal form: x³ + Ax² + Bx + C = 0
+    let a = c[2] / c[3];
+    let b = c[1] / c[3];
+    let cc = c[0] / c[3];
+
+    // substitute x = y - A/3 to eliminate the quadric term: y³ + 3py + 2q = 0
+    let sq_a = a * a;
+    let p = 1.0 / 3.0 * (-1.0 / 3.0 * sq_a + b);
+    let q = 1.0 / 2.0 * (2.0 / 27.0 * a * sq_a - 1.0 / 3.0 * a * b + cc);
+
+    // use Cardano's formula
+    let cb_p = p * p * p;
+    let d = q * q + cb_p;
+    let mut roots = if is_zero(d) {
+        if is_zero(q) {
+            vec![0.0]
+        } else {
+            let u = (-q).cbrt();
+            vec![2.0 * u, -u]
+        }
+    } else if d < 0.0 {
+        // three real solutions
+        let phi = 1.0 / 3.0 * (-q / (-cb_p).sqrt()).clamp(-1.0, 1.0).acos();
+        let t = 2.0 * (-p).sqrt();
+        vec![
+            t * phi.cos(),
+            -t * (phi + std::f64::consts::PI / 3.0).cos(),
+            -t * (phi - std::f64::consts::PI / 3.0).cos(),
+        ]
+    } else {
+        // one real solution
+        let sqrt_d = d.sqrt();
+        vec![(sqrt_d - q).cbrt() - (sqrt_d + q).cbrt()]
+    };
+
+    for root in roots.iter_mut() {
+        *root -= 1.0 / 3.0 * a;
+    }
+    roots
+}
+
+pub fn solve_quartic(c: [f64; 5]) -> Vec<f64> {
+    if is_zero(c[4]) {
+        return solve_cubic([c[0], c[1], c[2], c[3]]);
+    }
+    // normal form: x⁴ + Ax³ + Bx² + Cx + D = 0
+    let a = c[3] / c[4];
+    let b = c[2] / c[4];
+    let cc = c[1] / c[4];
+    let d = c[0] / c[4];
+
+    // substitute x = y - A/4 to eliminate the cubic term: y⁴ + py² + qy + r = 0
+    let sq_a = a * a;
+    let p = -3.0 / 8.0 * sq_a + b;
+    let q = 1.0 / 8.0 * sq_a * a - 1.0 / 2.0 * a * b + cc;
+    let r = -3.0 / 256.0 * sq_a * sq_a + 1.0 / 16.0 * sq_a * b - 1.0 / 4.0 * a * cc + d;
+
+    let mut roots = if is_zero(r) {
+        // no absolute term: y(y³ + py + q) = 0
+        let mut roots = solve_cubic([q, p, 0.0, 1.0]);
+        roots.push(0.0);
+        roots
+    } else {
+        // solve the resolvent cubic and use one of its real roots to split the quartic
+        let z = solve_cubic([
+            1.0 / 2.0 * r * p - 1.0 / 8.0 * q * q,
+            -r,
+            -1.0 / 2.0 * p,
+            1.0,
+        ])[0];
+
+        let u = z * z - r;
+        let v = 2.0 * z - p;
+        let u = if is_zero(u) {
+            0.0
+        } else if u > 0.0 {
+            u.sqrt()
+        } else {
+            return Vec::new();
+        };
+        let v = if is_zero(v) {
+            0.0
+        } else if v > 0.0 {
+            v.sqrt()
+        } else {
+            return Vec::new();
+        };
+
+        let mut roots = solve_quadratic([z - u, if q < 0.0 { -v } else { v }, 1.0]);
+        roots.extend(solve_quadratic([z + u, if q < 0.0 { v } else { -v }, 1.0]));
+        roots
+    };
+
+    for root in roots.iter_mut() {
+        *root -= 1.0 / 4.0 * a;
+        // polish against the original polynomial, since the closed form loses precision
+        for _ in 0..2 {
+            let x = *root;
+            let f = (((c[4] * x + c[3]) * x + c[2]) * x + c[1]) * x + c[0];
+            let df = ((4.0 * c[4] * x + 3.0 * c[3]) * x + 2.0 * c[2]) * x + c[1];
+            if df.abs() > EPSILON {
+                *root = x - f / df;
+            }
+        }
+    }
+    roots
+}
+
+#[cfg(test)]
+fn sorted(mut v: Vec<f64>) -> Vec<f64> {
+    v.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    v.dedup_by(|a, b| (*a - *b).abs() < 1e-6);
+    v
+}
+
+#[test]
+fn test_solve_quadratic() {
+    let roots = sorted(solve_quadratic([6.0, -5.0, 1.0]));
+    assert_eq!(roots.len(), 2);
+    assert!((roots[0] - 2.0).abs() < 1e-9);
+    assert!((roots[1] - 3.0).abs() < 1e-9);
+    assert!(solve_quadratic([1.0, 0.0, 1.0]).is_empty());
+}
+
+#[test]
+fn test_solve_cubic() {
+    // (x + 1)(x - 2)(x - 5)
+    let roots = sorted(solve_cubic([10.0, 3.0, -6.0, 1.0]));
+    assert_eq!(roots.len(), 3);
+    for (root, expected) in roots.iter().zip([-1.0, 2.0, 5.0].iter()) {
+        assert!((root - expected).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn test_solve_quartic() {
+    // (x - 1)(x - 2)(x - 3)(x - 4)
+    let roots = sorted(solve_quartic([24.0, -50.0, 35.0, -10.0, 1.0]));
+    assert_eq!(roots.len(), 4);
+    for (root, expected) in roots.iter().zip([1.0, 2.0, 3.0, 4.0].iter()) {
+        assert!((root - expected).abs() < 1e-9);
+    }
+    // (x² + 1)(x - 0.5)(x + 3)
+    let roots = sorted(solve_quartic([-1.5, 2.5, -0.5, 2.5, 1.0]));
+    assert_eq!(roots.len(), 2);
+    assert!((roots[0] + 3.0).abs() < 1e-9);
+    assert!((roots[1] - 0.5).abs() < 1e-9);
+}
@@ -0,0 +1,258 @@
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    hittable_list::HittableList,
+    material::Material,
+    ray::Ray,
+    vec3::{Point, Vec3},
+};
+use std::rc::Rc;
+
+// A parallelogram spanned by `u` and `v` from the corner `q`.
+#[derive(Clone)]
+pub struct Quad {
+    q: Point,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    normal: Vec3,
+    d: f64,
+    material: Rc<dyn Material>,
+}
+
+impl Quad {
+    pub fn new(q: Point, u: Vec3, v: Vec3, material: Rc<dyn Material>) -> Self {
+        let n = u.cross(&v);
+        let normal = n.unit();
+        Self {
+            q,
+            u,
+            v,
+            w: n / n.dot(&n),
+            normal,
+            d: normal.dot(&q),
+            material,
+        }
+    }
+    pub fn new_xy(x0: f64, x1: f64, y0: f64, y1: f64, k: f64, material: Rc<dyn Material>) -> Self {
+        Self::new(
+            Point::new(x0, y0, k),
+            Vec3::new(x1 - x0, 0.0, 0.0),
+            Vec3::new(0.0, y1 - y0, 0.0),
+            material,
+        )
+    }
+    pub fn new_xz(x0: f64, x1: f64, z0: f64, z1: f64, k: f64, material: Rc<dyn Material>) -> Self {
+        Self::new(
+            Point::new(x0, k, z0),
+            Vec3::new(0.0, 0.0, z1 - z0),
+            Vec3::new(x1 - x0, 0.0, 0.0),
+            material,
+        )
+    }
+    pub fn new_yz(y0: f64, y1: f64, z0: f64, z1: f64, k: f64, material: Rc<dyn Material>) -> Self {
+        Self::new(
+            Point::new(k, y0, z0),
+            Vec3::new(0.0, y1 - y0, 0.0),
+            Vec3::new(0.0, 0.0, z1 - z0),
+            material,
+        )
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let denom = self.normal.dot(&r.direction);
+        if denom.abs() < 1e-8 {
+            return false;
+        }
+        let t = (self.d - self.normal.dot(&r.origin)) / denom;
+        if t < t_min || t_max < t {
+            return false;
+        }
+
+        // Express the hit point in the (u, v) coordinates of the quad.
+        let intersection = r.at(t);
+        let planar_hitpt_vector = intersection - self.q;
+        let alpha = self.w.dot(&planar_hitpt_vector.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar_hitpt_vector));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return false;
+        }
+
+        rec.t = t;
+        rec.point = intersection;
+        rec.u = alpha;
+        rec.v = beta;
+        rec.set_face_normal(r, &self.normal);
+        rec.material = self.material.clone();
+        true
+    }
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        let corners = [
+            self.q,
+            self.q + self.u,
+            self.q + self.v,
+            self.q + self.u + self.v,
+        ];
+        *output_box = Aabb::from_points(&corners).padded(1e-4);
+        true
+    }
+}
+
+// An axis-aligned box with opposite corners `a` and `b`, made of six quads.
+#[derive(Clone)]
+pub struct Cuboid(HittableList<Quad>);
+
+impl Cuboid {
+    pub fn new(a: Point, b: Point, material: Rc<dyn Material>) -> Self {
+        let min = Point::new(a[0].min(b[0]), a[1].min(b[1]), a[2].min(b[2]));
+        let max = Point::new(a[0].max(b[0]), a[1].max(b[1]), a[2].max(b[2]));
+        let dx = Vec3::new(max[0] - min[0], 0.0, 0.0);
+        let dy = Vec3::new(0.0, max[1] - min[1], 0.0);
+        let dz = Vec3::new(0.0, 0.0, max[2] - min[2]);
+
+        let mut sides = HittableList::new();
+        sides.push(Quad::new(
+            Point::new(min[0], min[1], max[2]),
+            dx,
+            dy,
+            material.clone(),
+        )); // front
+        sides.push(Quad::new(
+            Point::new(max[0], min[1], max[2]),
+            -dz,
+            dy,
+            material.clone(),
+        )); // right
+        sides.push(Quad::new(
+            Point::new(max[0], min[1], min[2]),
+            -dx,
+            dy,
+            material.clone(),
+        )); // back
+        sides.push(Quad::new(
+            Point::new(min[0], min[1], min[2]),
+            dz,
+            dy,
+            material.clone(),
+        )); // left
+        sides.push(Quad::new(
+            Point::new(min[0], max[1], max[2]),
+            dx,
+            -dz,
+            material.clone(),
+        )); // top
+        sides.push(Quad::new(
+            Point::new(min[0], min[1], min[2]),
+            dx,
+            dz,
+            material,
+        )); // bottom
+        Self(sides)
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        self.0.hit(r, t_min, t_max, rec)
+    }
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        self.0.bounding_box(time0, time1, output_box)
+    }
+}
+
+#[test]
+fn test_quad() {
+    use crate::{material::Lambertian, vec3::Color};
+
+    let material: Rc<dyn Material> = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let ray = |origin: Point, direction: Vec3| Ray {
+        origin,
+        direction,
+        ..Ray::default()
+    };
+    let quad = Quad::new(
+        Point::default(),
+        Vec3::new(2.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        material.clone(),
+    );
+    let down = Vec3::new(0.0, 0.0, -1.0);
+    let mut rec = HitRecord::default();
+    assert!(quad.hit(
+        &ray(Point::new(0.5, 0.25, 5.0), down),
+        0.001,
+        f64::INFINITY,
+        &mut rec
+    ));
+    assert!((rec.t - 5.0).abs() < 1e-12 && rec.front_face);
+    assert!((rec.normal + down).length() < 1e-12);
+    assert!((rec.u - 0.25).abs() < 1e-12 && (rec.v - 0.25).abs() < 1e-12);
+    assert!(quad.hit(
+        &ray(Point::new(0.5, 0.25, -5.0), -down),
+        0.001,
+        f64::INFINITY,
+        &mut rec
+    ));
+    assert!(!rec.front_face && (rec.normal - down).length() < 1e-12);
+    assert!(!quad.hit(
+        &ray(Point::new(2.5, 0.5, 5.0), down),
+        0.001,
+        f64::INFINITY,
+        &mut rec
+    ));
+    let mut bounds = Aabb::default();
+    assert!(quad.bounding_box(0.0, 0.0, &mut bounds));
+    assert!(bounds.min()[2] < 0.0 && bounds.max()[2] > 0.0);
+
+    // The axis-aligned helpers face along the positive axis.
+    let x = Quad::new_yz(0.0, 1.0, 0.0, 1.0, 0.0, material.clone());
+    let y = Quad::new_xz(0.0, 1.0, 0.0, 1.0, 0.0, material.clone());
+    let z = Quad::new_xy(0.0, 1.0, 0.0, 1.0, 0.0, material);
+    for (quad, axis) in [(x, 0), (y, 1), (z, 2)].iter() {
+        let mut direction = Vec3::default();
+        direction[*axis] = -1.0;
+        let origin = Point::new(0.5, 0.5, 0.5) - 5.0 * direction;
+        assert!(quad.hit(&ray(origin, direction), 0.001, f64::INFINITY, &mut rec));
+        assert!(rec.front_face && (rec.normal + direction).length() < 1e-12);
+    }
+}
+
+#[test]
+fn test_cuboid() {
+    use crate::{material::Lambertian, vec3::Color};
+
+    let material = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let cuboid = Cuboid::new(Point::new(1.0, 2.0, 3.0), Point::default(), material);
+    let center = Point::new(0.5, 1.0, 1.5);
+    let mut rec = HitRecord::default();
+    // From outside along each axis, each face is hit from the front with an outward normal.
+    for axis in 0..3 {
+        for &sign in [-1.0, 1.0].iter() {
+            let mut outward = Vec3::default();
+            outward[axis] = sign;
+            let r = Ray {
+                origin: center + 10.0 * outward,
+                direction: -outward,
+                ..Ray::default()
+            };
+            assert!(cuboid.hit(&r, 0.001, f64::INFINITY, &mut rec));
+            let half = [0.5, 1.0, 1.5][axis];
+            assert!((rec.t - (10.0 - half)).abs() < 1e-12 && rec.front_face);
+            assert!((rec.normal - outward).length() < 1e-12);
+            assert!((0.0..=1.0).contains(&rec.u) && (0.0..=1.0).contains(&rec.v));
+        }
+    }
+    let inside = Ray {
+        origin: center,
+        direction: Vec3::new(1.0, 0.0, 0.0),
+        ..Ray::default()
+    };
+    assert!(cuboid.hit(&inside, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 0.5).abs() < 1e-12 && !rec.front_face);
+    let mut bounds = Aabb::default();
+    assert!(cuboid.bounding_box(0.0, 0.0, &mut bounds));
+    assert!((bounds.min() - Point::default()).length() < 1e-3);
+    assert!((bounds.max() - Point::new(1.0, 2.0, 3.0)).length() < 1e-3);
+}
@@ -0,0 +1,585 @@
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    onb::Onb,
+    polynomial::{solve_quadratic, solve_quartic},
+    ray::Ray,
+    vec3::{Point, Vec3},
+};
+use std::{f64::consts::PI, rc::Rc};
+
+// Local frame shared by the primitives below: the object's axis is the local z axis.
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    origin: Point,
+    basis: Onb,
+}
+
+impl Frame {
+    fn new(origin: Point, axis: Vec3) -> Self {
+        Self {
+            origin,
+            basis: Onb::new_from_w(&axis),
+        }
+    }
+    fn bounding_box(&self, local_min: Point, local_max: Point) -> Aabb {
+        let corners = Aabb::new(local_min, local_max).corners();
+        let world: Vec<Point> = corners
+            .iter()
+            .map(|c| self.origin + self.basis.local(c))
+            .collect();
+        Aabb::from_points(&world).padded(1e-4)
+    }
+    fn transform_ray(&self, r: &Ray) -> Ray {
+        Ray {
+            origin: self.basis.to_local(&(r.origin - self.origin)),
+            direction: self.basis.to_local(&r.direction),
+            wavelength: r.wavelength,
+            time: r.time,
+        }
+    }
+}
+
+fn azimuth(p: &Point) -> f64 {
+    let phi = p[1].atan2(p[0]);
+    if phi < 0.0 {
+        phi + 2.0 * PI
+    } else {
+        phi
+    }
+}
+
+// Candidate intersection in local coordinates.
+struct LocalHit {
+    t: f64,
+    normal: Vec3,
+    u: f64,
+    v: f64,
+}
+
+fn record(
+    frame: &Frame,
+    r: &Ray,
+    hit: LocalHit,
+    material: &Rc<dyn Material>,
+    rec: &mut HitRecord,
+) -> bool {
+    rec.t = hit.t;
+    rec.point = r.at(hit.t);
+    rec.u = hit.u;
+    rec.v = hit.v;
+    rec.set_face_normal(r, &frame.basis.local(&hit.normal).unit());
+    rec.material = material.clone();
+    true
+}
+
+fn closest(a: Option<LocalHit>, b: Option<LocalHit>) -> Option<LocalHit> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if a.t <= b.t { a } else { b }),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+// Intersects the plane z = height with an annulus between inner_radius and radius.
+fn hit_cap(
+    r: &Ray,
+    height: f64,
+    inner_radius: f64,
+    radius: f64,
+    normal: Vec3,
+    t_min: f64,
+    t_max: f64,
+) -> Option<LocalHit> {
+    if r.direction[2].abs() < 1e-12 {
+        return None;
+    }
+    let t = (height - r.origin[2]) / r.direction[2];
+    if t < t_min || t_max < t {
+        return None;
+    }
+    let p = r.at(t);
+    let dist2 = p[0] * p[0] + p[1] * p[1];
+    if dist2 > radius * radius || dist2 < inner_radius * inner_radius {
+        return None;
+    }
+    Some(LocalHit {
+        t,
+        normal,
+        u: azimuth(&p) / (2.0 * PI),
+        v: (radius - dist2.sqrt()) / (radius - inner_radius),
+    })
+}
+
+// Closest root of a quadric whose hit point lies within 0 <= z <= height.
+fn hit_quadric_side<F>(
+    r: &Ray,
+    coefficients: [f64; 3],
+    height: f64,
+    t_min: f64,
+    t_max: f64,
+    surface: F,
+) -> Option<LocalHit>
+where
+    F: Fn(&Point, f64) -> LocalHit,
+{
+    let mut roots = solve_quadratic(coefficients);
+    roots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    roots
+        .into_iter()
+        .filter(|t| t_min <= *t && *t <= t_max)
+        .find(|t| (0.0..=height).contains(&r.at(*t)[2]))
+        .map(|t| surface(&r.at(t), t))
+}
+
+// A flat disk, or an annulus when inner_radius is positive.
+#[derive(Clone)]
+pub struct Disk {
+    frame: Frame,
+    radius: f64,
+    inner_radius: f64,
+    material: Rc<dyn Material>,
+}
+
+impl Disk {
+    pub fn new(center: Point, normal: Vec3, radius: f64, material: Rc<dyn Material>) -> Self {
+        Self::new_annulus(center, normal, 0.0, radius, material)
+    }
+    pub fn new_annulus(
+        center: Point,
+        normal: Vec3,
+        inner_radius: f64,
+        radius: f64,
+        material: Rc<dyn Material>,
+    ) -> Self {
+        Self {
+            frame: Frame::new(center, normal),
+            radius,
+            inner_radius,
+            material,
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let local = self.frame.transform_ray(r);
+        match hit_cap(
+            &local,
+            0.0,
+            self.inner_radius,
+            self.radius,
+            Vec3::new(0.0, 0.0, 1.0),
+            t_min,
+            t_max,
+        ) {
+            Some(hit) => record(&self.frame, r, hit, &self.material, rec),
+            None => false,
+        }
+    }
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        *output_box = self.frame.bounding_box(
+            Point::new(-self.radius, -self.radius, 0.0),
+            Point::new(self.radius, self.radius, 0.0),
+        );
+        true
+    }
+}
+
+// A cylinder around `axis` from `base` up to `height`, optionally closed with caps.
+#[derive(Clone)]
+pub struct Cylinder {
+    frame: Frame,
+    radius: f64,
+    height: f64,
+    capped: bool,
+    material: Rc<dyn Material>,
+}
+
+impl Cylinder {
+    pub fn new(
+        base: Point,
+        axis: Vec3,
+        radius: f64,
+        height: f64,
+        capped: bool,
+        material: Rc<dyn Material>,
+    ) -> Self {
+        Self {
+            frame: Frame::new(base, axis),
+            radius,
+            height,
+            capped,
+            material,
+        }
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let l = self.frame.transform_ray(r);
+        let (o, d) = (l.origin, l.direction);
+        let coefficients = [
+            o[0] * o[0] + o[1] * o[1] - self.radius * self.radius,
+            2.0 * (o[0] * d[0] + o[1] * d[1]),
+            d[0] * d[0] + d[1] * d[1],
+        ];
+        let mut hit = hit_quadric_side(&l, coefficients, self.height, t_min, t_max, |p, t| {
+            LocalHit {
+                t,
+                normal: Vec3::new(p[0], p[1], 0.0) / self.radius,
+                u: azimuth(p) / (2.0 * PI),
+                v: p[2] / self.height,
+            }
+        });
+        if self.capped {
+            let z = Vec3::new(0.0, 0.0, 1.0);
+            hit = closest(hit, hit_cap(&l, 0.0, 0.0, self.radius, -z, t_min, t_max));
+            hit = closest(
+                hit,
+                hit_cap(&l, self.height, 0.0, self.radius, z, t_min, t_max),
+            );
+        }
+        match hit {
+            Some(hit) => record(&self.frame, r, hit, &self.material, rec),
+            None => false,
+        }
+    }
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        *output_box = self.frame.bounding_box(
+            Point::new(-self.radius, -self.radius, 0.0),
+            Point::new(self.radius, self.radius, self.height),
+        );
+        true
+    }
+}
+
+// A cone with its base disk at `base` and its apex `height` along `axis`.
+#[derive(Clone)]
+pub struct Cone {
+    frame: Frame,
+    radius: f64,
+    height: f64,
+    capped: bool,
+    material: Rc<dyn Material>,
+}
+
+impl Cone {
+    pub fn new(
+        base: Point,
+        axis: Vec3,
+        radius: f64,
+        height: f64,
+        capped: bool,
+        material: Rc<dyn Material>,
+    ) -> Self {
+        Self {
+            frame: Frame::new(base, axis),
+            radius,
+            height,
+            capped,
+            material,
+        }
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let l = self.frame.transform_ray(r);
+        let (o, d) = (l.origin, l.direction);
+        // x² + y² = k² (h - z)²
+        let k2 = (self.radius / self.height) * (self.radius / self.height);
+        let hz = self.height - o[2];
+        let coefficients = [
+            o[0] * o[0] + o[1] * o[1] - k2 * hz * hz,
+            2.0 * (o[0] * d[0] + o[1] * d[1] + k2 * hz * d[2]),
+            d[0] * d[0] + d[1] * d[1] - k2 * d[2] * d[2],
+        ];
+        let mut hit = hit_quadric_side(&l, coefficients, self.height, t_min, t_max, |p, t| {
+            LocalHit {
+                t,
+                normal: Vec3::new(p[0], p[1], k2 * (self.height - p[2])).unit(),
+                u: azimuth(p) / (2.0 * PI),
+                v: p[2] / self.height,
+            }
+        });
+        if self.capped {
+            let cap = hit_cap(
+                &l,
+                0.0,
+                0.0,
+                self.radius,
+                Vec3::new(0.0, 0.0, -1.0),
+                t_min,
+                t_max,
+            );
+            hit = closest(hit, cap);
+        }
+        match hit {
+            Some(hit) => record(&self.frame, r, hit, &self.material, rec),
+            None => false,
+        }
+    }
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        *output_box = self.frame.bounding_box(
+            Point::new(-self.radius, -self.radius, 0.0),
+            Point::new(self.radius, self.radius, self.height),
+        );
+        true
+    }
+}
+
+// A paraboloid z = height (x² + y²) / radius², opening along `axis` from its vertex at `base`.
+#[derive(Clone)]
+pub struct Paraboloid {
+    frame: Frame,
+    radius: f64,
+    height: f64,
+    capped: bool,
+    material: Rc<dyn Material>,
+}
+
+impl Paraboloid {
+    pub fn new(
+        base: Point,
+        axis: Vec3,
+        radius: f64,
+        height: f64,
+        capped: bool,
+        material: Rc<dyn Material>,
+    ) -> Self {
+        Self {
+            frame: Frame::new(base, axis),
+            radius,
+            height,
+            capped,
+            material,
+        }
+    }
+}
+
+impl Hittable for Paraboloid {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let l = self.frame.transform_ray(r);
+        let (o, d) = (l.origin, l.direction);
+        let k = self.height / (self.radius * self.radius);
+        let coefficients = [
+            k * (o[0] * o[0] + o[1] * o[1]) - o[2],
+            2.0 * k * (o[0] * d[0] + o[1] * d[1]) - d[2],
+            k * (d[0] * d[0] + d[1] * d[1]),
+        ];
+        let mut hit = hit_quadric_side(&l, coefficients, self.height, t_min, t_max, |p, t| {
+            LocalHit {
+                t,
+                normal: Vec3::new(2.0 * k * p[0], 2.0 * k * p[1], -1.0).unit(),
+                u: azimuth(p) / (2.0 * PI),
+                v: p[2] / self.height,
+            }
+        });
+        if self.capped {
+            let cap = hit_cap(
+                &l,
+                self.height,
+                0.0,
+                self.radius,
+                Vec3::new(0.0, 0.0, 1.0),
+                t_min,
+                t_max,
+            );
+            hit = closest(hit, cap);
+        }
+        match hit {
+            Some(hit) => record(&self.frame, r, hit, &self.material, rec),
+            None => false,
+        }
+    }
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        *output_box = self.frame.bounding_box(
+            Point::new(-self.radius, -self.radius, 0.0),
+            Point::new(self.radius, self.radius, self.height),
+        );
+        true
+    }
+}
+
+// A torus around `axis`, with the tube of `minor_radius` centered `major_radius` from `center`.
+#[derive(Clone)]
+pub struct Torus {
+    frame: Frame,
+    major_radius: f64,
+    minor_radius: f64,
+    material: Rc<dyn Material>,
+}
+
+impl Torus {
+    pub fn new(
+        center: Point,
+        axis: Vec3,
+        major_radius: f64,
+        minor_radius: f64,
+        material: Rc<dyn Material>,
+    ) -> Self {
+        Self {
+            frame: Frame::new(center, axis),
+            major_radius,
+            minor_radius,
+            material,
+        }
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let l = self.frame.transform_ray(r);
+        let (big_r2, small_r2) = (
+            self.major_radius * self.major_radius,
+            self.minor_radius * self.minor_radius,
+        );
+
+        // March along the unit direction, so the leading coefficient of the quartic is 1 however
+        // long the ray direction is, and move the origin up to the bounding sphere so the quartic
+        // stays well conditioned.
+        let speed = l.direction.length();
+        let d = l.direction / speed;
+        let bound = self.major_radius + self.minor_radius;
+        let sphere = solve_quadratic([
+            l.origin.dot(&l.origin) - bound * bound,
+            2.0 * l.origin.dot(&d),
+            1.0,
+        ]);
+        if sphere.len() < 2 {
+            return false;
+        }
+        let shift = sphere[0].min(sphere[1]).max(0.0);
+        let o = l.origin + shift * d;
+
+        // (|p|² - R² - r²)² + 4R² (z² - r²) = 0
+        let e = o.dot(&o) - big_r2 - small_r2;
+        let f = o.dot(&d);
+        let roots = solve_quartic([
+            e * e + 4.0 * big_r2 * (o[2] * o[2] - small_r2),
+            4.0 * f * e + 8.0 * big_r2 * o[2] * d[2],
+            4.0 * f * f + 2.0 * e + 4.0 * big_r2 * d[2] * d[2],
+            4.0 * f,
+            1.0,
+        ]);
+        let t = match roots
+            .into_iter()
+            .map(|s| (s + shift) / speed)
+            .filter(|t| t_min <= *t && *t <= t_max)
+            .fold(None, |closest: Option<f64>, t| match closest {
+                Some(c) if c <= t => Some(c),
+                _ => Some(t),
+            }) {
+            Some(t) => t,
+            None => return false,
+        };
+
+        let p = l.at(t);
+        let a = p.dot(&p) - big_r2 - small_r2;
+        let normal = (a * p + Vec3::new(0.0, 0.0, 2.0 * big_r2 * p[2])).unit();
+        let tube = p[2].atan2((p[0] * p[0] + p[1] * p[1]).sqrt() - self.major_radius);
+        let hit = LocalHit {
+            t,
+            normal,
+            u: azimuth(&p) / (2.0 * PI),
+            v: (tube + PI) / (2.0 * PI),
+        };
+        record(&self.frame, r, hit, &self.material, rec)
+    }
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        let extent = self.major_radius + self.minor_radius;
+        *output_box = self.frame.bounding_box(
+            Point::new(-extent, -extent, -self.minor_radius),
+            Point::new(extent, extent, self.minor_radius),
+        );
+        true
+    }
+}
+
+#[test]
+fn test_quadrics() {
+    use crate::{material::Lambertian, vec3::Color};
+
+    let material: Rc<dyn Material> = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let z = Vec3::new(0.0, 0.0, 1.0);
+    let ray = |origin: Point, direction: Vec3| Ray {
+        origin,
+        direction,
+        ..Ray::default()
+    };
+    let close = |a: Vec3, b: Vec3| (a - b).length() < 1e-9;
+    let hit = |object: &dyn Hittable, r: &Ray| {
+        let mut rec = HitRecord::default();
+        if object.hit(r, 0.001, f64::INFINITY, &mut rec) {
+            Some(rec)
+        } else {
+            None
+        }
+    };
+    // Every primitive below is built around +z, where the local frame has u along -x and v
+    // along +y; points on the +y side therefore sit a quarter turn round in u.
+
+    let disk = Disk::new(Point::default(), z, 1.0, material.clone());
+    let rec = hit(&disk, &ray(Point::new(0.0, 0.5, 5.0), -2.0 * z)).unwrap();
+    assert!((rec.t - 2.5).abs() < 1e-9 && rec.front_face);
+    assert!(close(rec.normal, z));
+    assert!((rec.u - 0.25).abs() < 1e-9 && (rec.v - 0.5).abs() < 1e-9);
+    let rec = hit(&disk, &ray(Point::new(0.0, 0.5, -5.0), z)).unwrap();
+    assert!(!rec.front_face && close(rec.normal, -z));
+    let annulus = Disk::new_annulus(Point::default(), z, 0.6, 1.0, material.clone());
+    assert!(hit(&annulus, &ray(Point::new(0.0, 0.5, 5.0), -z)).is_none());
+    assert!(hit(&annulus, &ray(Point::new(0.0, 0.8, 5.0), -z)).is_some());
+
+    let side = |height: f64| ray(Point::new(0.0, 5.0, height), Vec3::new(0.0, -1.0, 0.0));
+    let open = Cylinder::new(Point::default(), z, 1.0, 2.0, false, material.clone());
+    let rec = hit(&open, &side(1.0)).unwrap();
+    assert!((rec.t - 4.0).abs() < 1e-9 && rec.front_face);
+    assert!(close(rec.normal, Vec3::new(0.0, 1.0, 0.0)));
+    assert!((rec.u - 0.25).abs() < 1e-9 && (rec.v - 0.5).abs() < 1e-9);
+    let rec = hit(
+        &open,
+        &ray(Point::new(0.0, 0.0, 1.0), Vec3::new(0.0, 1.0, 0.0)),
+    )
+    .unwrap();
+    assert!((rec.t - 1.0).abs() < 1e-9 && !rec.front_face);
+    assert!(close(rec.normal, Vec3::new(0.0, -1.0, 0.0)));
+    // Looking down the axis, only the caps are in the way.
+    let down = ray(Point::new(0.0, 0.5, 5.0), -z);
+    assert!(hit(&open, &down).is_none());
+    let closed = Cylinder::new(Point::default(), z, 1.0, 2.0, true, material.clone());
+    let rec = hit(&closed, &down).unwrap();
+    assert!((rec.t - 3.0).abs() < 1e-9 && rec.front_face && close(rec.normal, z));
+
+    let cone = Cone::new(Point::default(), z, 1.0, 1.0, true, material.clone());
+    let rec = hit(&cone, &side(0.5)).unwrap();
+    assert!((rec.t - 4.5).abs() < 1e-9 && rec.front_face);
+    assert!(close(rec.normal, Vec3::new(0.0, 1.0, 1.0).unit()));
+    assert!((rec.u - 0.25).abs() < 1e-9 && (rec.v - 0.5).abs() < 1e-9);
+    let rec = hit(&cone, &ray(Point::new(0.0, 0.2, -5.0), z)).unwrap();
+    assert!((rec.t - 5.0).abs() < 1e-9 && rec.front_face && close(rec.normal, -z));
+
+    let paraboloid = Paraboloid::new(Point::default(), z, 1.0, 1.0, true, material.clone());
+    let rec = hit(&paraboloid, &side(0.25)).unwrap();
+    assert!((rec.t - 4.5).abs() < 1e-9 && rec.front_face);
+    assert!(close(rec.normal, Vec3::new(0.0, 1.0, -1.0).unit()));
+    assert!((rec.u - 0.25).abs() < 1e-9 && (rec.v - 0.25).abs() < 1e-9);
+    let rec = hit(&paraboloid, &down).unwrap();
+    assert!((rec.t - 4.0).abs() < 1e-9 && rec.front_face && close(rec.normal, z));
+
+    let torus = Torus::new(Point::default(), z, 2.0, 0.5, material);
+    let rec = hit(&torus, &side(0.0)).unwrap();
+    assert!((rec.t - 2.5).abs() < 1e-9 && rec.front_face);
+    assert!(close(rec.normal, Vec3::new(0.0, 1.0, 0.0)));
+    assert!((rec.u - 0.25).abs() < 1e-9 && (rec.v - 0.5).abs() < 1e-9);
+    assert!(hit(&torus, &ray(Point::new(0.0, 0.0, 5.0), -z)).is_none());
+    let rec = hit(&torus, &ray(Point::new(0.0, 2.0, 5.0), -z)).unwrap();
+    assert!((rec.t - 4.5).abs() < 1e-9 && close(rec.normal, z));
+    // The hit does not depend on the length of the ray direction.
+    for &scale in [1e-6, 1e3].iter() {
+        let r = ray(Point::new(0.0, 5.0, 0.0), Vec3::new(0.0, -scale, 0.0));
+        let rec = hit(&torus, &r).unwrap();
+        assert!((rec.t * scale - 2.5).abs() < 1e-9);
+    }
+}
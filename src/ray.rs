@@ -1,5 +1,6 @@
 use crate::{
     hittable::{HitRecord, Hittable},
+    spectrum::{rgb_to_illuminant, rgb_to_reflectance},
     vec3::Color,
     vec3::{Direction, Point},
 };
@@ -8,6 +9,9 @@ use crate::{
 pub struct Ray {
     pub origin: Point,
     pub direction: Direction,
+    // Wavelength in nanometers once the path has been restricted to a single wavelength.
+    pub wavelength: Option<f64>,
+    pub time: f64,
 }
 
 impl Ray {
@@ -22,18 +26,51 @@ impl Ray {
             return Color::default();
         }
         let mut rec = HitRecord::default();
-        if world.hit(&self, 0.001, std::f64::INFINITY, &mut rec) {
+        if world.hit(self, 0.001, f64::INFINITY, &mut rec) {
             let mut scattered = Ray::default();
             let mut attenuation = Color::default();
+            let emitted = rec.material.emitted(rec.u, rec.v, &rec.point);
             if rec
                 .material
-                .scatter(&self, &rec, &mut attenuation, &mut scattered, rng)
+                .scatter(self, &rec, &mut attenuation, &mut scattered, rng)
             {
-                return attenuation * scattered.color(world, rng, depth - 1);
+                return emitted + attenuation * scattered.color(world, rng, depth - 1);
             } else {
-                return Color::default();
+                return emitted;
             }
         }
+        self.background()
+    }
+    // Radiance at `self.wavelength`, which must be set by the caller.
+    pub fn spectral_radiance<T>(&self, world: &T, rng: &mut dyn rand::RngCore, depth: i64) -> f64
+    where
+        T: Hittable,
+    {
+        let wavelength = self.wavelength.expect("spectral rays carry a wavelength");
+        if depth <= 0 {
+            return 0.0;
+        }
+        let mut rec = HitRecord::default();
+        if world.hit(self, 0.001, f64::INFINITY, &mut rec) {
+            let mut scattered = Ray::default();
+            let mut attenuation = Color::default();
+            let emitted = rec
+                .material
+                .emitted_spectral(rec.u, rec.v, &rec.point, wavelength);
+            if rec
+                .material
+                .scatter(self, &rec, &mut attenuation, &mut scattered, rng)
+            {
+                return emitted
+                    + rgb_to_reflectance(&attenuation, wavelength)
+                        * scattered.spectral_radiance(world, rng, depth - 1);
+            } else {
+                return emitted;
+            }
+        }
+        rgb_to_illuminant(&self.background(), wavelength)
+    }
+    pub fn background(&self) -> Color {
         let unit_direction = self.direction.unit();
         let t = 0.5 * (unit_direction[1] + 1.0);
         (1.0 - t) * Color::new(1.0, 1.0, 1.0) + t * Color::new(0.5, 0.7, 1.0)
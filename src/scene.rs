@@ -0,0 +1,269 @@
+use crate::{
+    aabb::Aabb,
+    bvh::Bvh,
+    camera::CameraSettings,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    ray::Ray,
+    sphere::Sphere,
+    vec3::{Color, Direction, Point},
+};
+use rand::RngCore;
+use std::{f64::consts::PI, rc::Rc};
+
+const ENVIRONMENT_RADIUS: f64 = 1e7;
+const DISTANT_LIGHT_ANGULAR_RADIUS: f64 = 0.5; // degrees, roughly the sun
+
+#[derive(Debug, Clone)]
+pub enum Light {
+    // Radiant intensity, in power per steradian.
+    Point {
+        position: Point,
+        intensity: Color,
+    },
+    // Angles are half-angles of the cone in radians; the falloff is smooth in between.
+    Spot {
+        position: Point,
+        direction: Direction,
+        intensity: Color,
+        inner_angle: f64,
+        outer_angle: f64,
+    },
+    // `direction` points towards the light.
+    Distant {
+        direction: Direction,
+        irradiance: Color,
+    },
+    Infinite {
+        radiance: Color,
+    },
+}
+
+// Everything a scene importer produces. Objects are in world space.
+#[derive(Default)]
+pub struct Scene {
+    pub objects: Vec<Box<dyn Hittable>>,
+    pub cameras: Vec<CameraSettings>,
+    pub resolution: Option<(usize, usize)>,
+    pub lights: Vec<Light>,
+    // Parts of the input that were skipped or approximated.
+    pub warnings: Vec<String>,
+}
+
+impl Scene {
+    // The path tracer only finds light by hitting it, so point and spot lights become small
+    // emissive spheres of `light_radius`, and distant and infinite lights an emissive
+    // environment far away.
+    pub fn into_world(self, light_radius: f64) -> Bvh<Box<dyn Hittable>> {
+        let mut objects = self.objects;
+        let mut environment = Environment::default();
+        for light in self.lights.into_iter() {
+            match light {
+                Light::Point {
+                    position,
+                    intensity,
+                } => objects.push(Box::new(Sphere::new(
+                    position,
+                    light_radius,
+                    Rc::new(PunctualEmitter::new(
+                        position,
+                        intensity,
+                        light_radius,
+                        None,
+                    )),
+                ))),
+                Light::Spot {
+                    position,
+                    direction,
+                    intensity,
+                    inner_angle,
+                    outer_angle,
+                } => {
+                    let spot = (direction.unit(), inner_angle.cos(), outer_angle.cos());
+                    objects.push(Box::new(Sphere::new(
+                        position,
+                        light_radius,
+                        Rc::new(PunctualEmitter::new(
+                            position,
+                            intensity,
+                            light_radius,
+                            Some(spot),
+                        )),
+                    )))
+                }
+                Light::Distant {
+                    direction,
+                    irradiance,
+                } => {
+                    let cos_radius = DISTANT_LIGHT_ANGULAR_RADIUS.to_radians().cos();
+                    let solid_angle = 2.0 * PI * (1.0 - cos_radius);
+                    environment
+                        .suns
+                        .push((direction.unit(), irradiance / solid_angle, cos_radius));
+                }
+                Light::Infinite { radiance } => environment.radiance += radiance,
+            }
+        }
+        if environment.radiance.length_squared() > 0.0 || !environment.suns.is_empty() {
+            objects.push(Box::new(EnvironmentSphere {
+                material: Rc::new(environment),
+            }));
+        }
+        Bvh::new(objects, 0.0, 1.0)
+    }
+}
+
+#[derive(Clone)]
+struct PunctualEmitter {
+    center: Point,
+    radiance: Color,
+    spot: Option<(Direction, f64, f64)>,
+}
+
+impl PunctualEmitter {
+    fn new(
+        center: Point,
+        intensity: Color,
+        radius: f64,
+        spot: Option<(Direction, f64, f64)>,
+    ) -> Self {
+        // A sphere of radiance L has intensity L * pi * r^2 in every direction.
+        Self {
+            center,
+            radiance: intensity / (PI * radius * radius),
+            spot,
+        }
+    }
+}
+
+impl Material for PunctualEmitter {
+    fn scatter(
+        &self,
+        _r_in: &Ray,
+        _rec: &HitRecord,
+        _attenuation: &mut Color,
+        _scattered: &mut Ray,
+        _rng: &mut dyn RngCore,
+    ) -> bool {
+        false
+    }
+    fn emitted(&self, _u: f64, _v: f64, p: &Point) -> Color {
+        match self.spot {
+            Some((direction, cos_inner, cos_outer)) => {
+                let cos_theta = (*p - self.center).unit().dot(&direction);
+                let x =
+                    ((cos_theta - cos_outer) / (cos_inner - cos_outer).max(1e-9)).clamp(0.0, 1.0);
+                self.radiance * (x * x * (3.0 - 2.0 * x))
+            }
+            None => self.radiance,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct Environment {
+    radiance: Color,
+    // (direction, radiance, cosine of the angular radius)
+    suns: Vec<(Direction, Color, f64)>,
+}
+
+impl Material for Environment {
+    fn scatter(
+        &self,
+        _r_in: &Ray,
+        _rec: &HitRecord,
+        _attenuation: &mut Color,
+        _scattered: &mut Ray,
+        _rng: &mut dyn RngCore,
+    ) -> bool {
+        false
+    }
+    fn emitted(&self, _u: f64, _v: f64, p: &Point) -> Color {
+        let direction = p.unit();
+        self.suns
+            .iter()
+            .filter(|(sun, _, cos_radius)| direction.dot(sun) >= *cos_radius)
+            .fold(self.radiance, |sum, (_, radiance, _)| sum + *radiance)
+    }
+}
+
+// A huge sphere around the origin seen from inside; the hit point gives the direction.
+#[derive(Clone)]
+struct EnvironmentSphere {
+    material: Rc<dyn Material>,
+}
+
+impl Hittable for EnvironmentSphere {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let a = r.direction.length_squared();
+        let half_b = r.origin.dot(&r.direction);
+        let c = r.origin.length_squared() - ENVIRONMENT_RADIUS * ENVIRONMENT_RADIUS;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return false;
+        }
+        let t = (-half_b + discriminant.sqrt()) / a;
+        if t < t_min || t_max < t {
+            return false;
+        }
+        rec.t = t;
+        rec.point = r.at(t);
+        let outward_normal = rec.point / ENVIRONMENT_RADIUS;
+        rec.set_face_normal(r, &outward_normal);
+        rec.u = 0.0;
+        rec.v = 0.0;
+        rec.vertex_color = None;
+        rec.material = self.material.clone();
+        true
+    }
+    fn bounding_box(&self, _time0: f64, _time1: f64, _output_box: &mut Aabb) -> bool {
+        false
+    }
+}
+
+#[test]
+fn test_into_world() {
+    use crate::vec3::Vec3;
+
+    let intensity = Color::new(4.0, 2.0, 1.0);
+    let irradiance = Color::new(3.0, 3.0, 3.0);
+    let sun = Vec3::new(1.0, 1.0, 0.0).unit();
+    let scene = Scene {
+        lights: vec![
+            Light::Point {
+                position: Point::default(),
+                intensity,
+            },
+            Light::Distant {
+                direction: sun,
+                irradiance,
+            },
+        ],
+        ..Scene::default()
+    };
+    let radius = 0.1;
+    let world = scene.into_world(radius);
+    let emitted = |origin: Point, direction: Vec3| {
+        let r = Ray {
+            origin,
+            direction,
+            ..Ray::default()
+        };
+        let mut rec = HitRecord::default();
+        assert!(world.hit(&r, 0.001, f64::INFINITY, &mut rec));
+        (rec.t, rec.material.emitted(rec.u, rec.v, &rec.point))
+    };
+
+    // The emitter sphere seen from afar radiates the requested intensity.
+    let (t, radiance) = emitted(Point::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!((t - (5.0 - radius)).abs() < 1e-9);
+    assert!((radiance * PI * radius * radius - intensity).length() < 1e-9);
+
+    // Looking towards the sun gives its irradiance spread over its disk; elsewhere is dark.
+    let cos_radius = DISTANT_LIGHT_ANGULAR_RADIUS.to_radians().cos();
+    let solid_angle = 2.0 * PI * (1.0 - cos_radius);
+    let (_, radiance) = emitted(Point::new(0.0, 0.0, 1.0), sun);
+    assert!((radiance * solid_angle - irradiance).length() < 1e-6);
+    let (_, radiance) = emitted(Point::new(0.0, 0.0, 1.0), -sun);
+    assert!(radiance.length() < 1e-12);
+}
@@ -0,0 +1,359 @@
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    ray::Ray,
+    vec3::{Point, Vec3},
+};
+use std::rc::Rc;
+
+const MAX_STEPS: usize = 512;
+const SURFACE_EPSILON: f64 = 1e-4;
+const GRADIENT_EPSILON: f64 = 1e-5;
+
+// Signed distance to a surface: negative inside, positive outside. Sphere tracing relies on
+// the value never overestimating the true distance.
+pub trait Sdf {
+    fn distance(&self, p: &Point) -> f64;
+}
+
+impl<T> Sdf for Box<T>
+where
+    T: Sdf + ?Sized,
+{
+    fn distance(&self, p: &Point) -> f64 {
+        (**self).distance(p)
+    }
+}
+
+impl<T> Sdf for Rc<T>
+where
+    T: Sdf + ?Sized,
+{
+    fn distance(&self, p: &Point) -> f64 {
+        (**self).distance(p)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SdfSphere {
+    center: Point,
+    radius: f64,
+}
+
+impl SdfSphere {
+    pub fn new(center: Point, radius: f64) -> Self {
+        Self { center, radius }
+    }
+}
+
+impl Sdf for SdfSphere {
+    fn distance(&self, p: &Point) -> f64 {
+        (*p - self.center).length() - self.radius
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RoundedBox {
+    center: Point,
+    half_extents: Vec3,
+    radius: f64,
+}
+
+impl RoundedBox {
+    // half_extents include the rounding, so the box keeps its overall size.
+    pub fn new(center: Point, half_extents: Vec3, radius: f64) -> Self {
+        Self {
+            center,
+            half_extents,
+            radius,
+        }
+    }
+}
+
+impl Sdf for RoundedBox {
+    fn distance(&self, p: &Point) -> f64 {
+        let local = *p - self.center;
+        let mut q = Vec3::default();
+        for i in 0..3 {
+            q[i] = local[i].abs() - (self.half_extents[i] - self.radius);
+        }
+        let outside = Vec3::new(q[0].max(0.0), q[1].max(0.0), q[2].max(0.0));
+        let inside = q[0].max(q[1]).max(q[2]).min(0.0);
+        outside.length() + inside - self.radius
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Mandelbulb {
+    center: Point,
+    scale: f64,
+    power: f64,
+    iterations: usize,
+}
+
+impl Mandelbulb {
+    // The classic bulb uses power 8 and fits in a sphere of radius about 1.2 * scale.
+    pub fn new(center: Point, scale: f64, power: f64, iterations: usize) -> Self {
+        Self {
+            center,
+            scale,
+            power,
+            iterations,
+        }
+    }
+}
+
+impl Sdf for Mandelbulb {
+    fn distance(&self, p: &Point) -> f64 {
+        let c = (*p - self.center) / self.scale;
+        let mut z = c;
+        let mut dr = 1.0;
+        let mut r = z.length();
+        for _ in 0..self.iterations {
+            if r > 2.0 {
+                break;
+            }
+            let theta = (z[2] / r).acos() * self.power;
+            let phi = z[1].atan2(z[0]) * self.power;
+            dr = r.powf(self.power - 1.0) * self.power * dr + 1.0;
+            let zr = r.powf(self.power);
+            z =
+                zr * Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                ) + c;
+            r = z.length();
+        }
+        if r == 0.0 {
+            return 0.0;
+        }
+        0.5 * r.ln() * r / dr * self.scale
+    }
+}
+
+// Polynomial smooth minimum; k is the width of the blend region.
+#[derive(Debug, Clone)]
+pub struct SmoothUnion<A, B> {
+    a: A,
+    b: B,
+    k: f64,
+}
+
+impl<A, B> SmoothUnion<A, B>
+where
+    A: Sdf,
+    B: Sdf,
+{
+    pub fn new(a: A, b: B, k: f64) -> Self {
+        Self { a, b, k }
+    }
+}
+
+impl<A, B> Sdf for SmoothUnion<A, B>
+where
+    A: Sdf,
+    B: Sdf,
+{
+    fn distance(&self, p: &Point) -> f64 {
+        let d1 = self.a.distance(p);
+        let d2 = self.b.distance(p);
+        if self.k <= 0.0 {
+            return d1.min(d2);
+        }
+        let h = (0.5 + 0.5 * (d2 - d1) / self.k).clamp(0.0, 1.0);
+        d2 + (d1 - d2) * h - self.k * h * (1.0 - h)
+    }
+}
+
+// Infinite tiling of the inner shape. A zero period component leaves that axis alone.
+// The inner shape should fit within one cell for the distance to stay conservative.
+#[derive(Debug, Clone)]
+pub struct Repetition<S> {
+    sdf: S,
+    period: Vec3,
+}
+
+impl<S> Repetition<S>
+where
+    S: Sdf,
+{
+    pub fn new(sdf: S, period: Vec3) -> Self {
+        Self { sdf, period }
+    }
+}
+
+impl<S> Sdf for Repetition<S>
+where
+    S: Sdf,
+{
+    fn distance(&self, p: &Point) -> f64 {
+        let mut q = *p;
+        for i in 0..3 {
+            if self.period[i] > 0.0 {
+                q[i] -= self.period[i] * (p[i] / self.period[i]).round();
+            }
+        }
+        self.sdf.distance(&q)
+    }
+}
+
+// Rotates about the y axis by rate radians per unit of height. This stretches distances, so
+// objects using it need a step scale below one.
+#[derive(Debug, Clone)]
+pub struct Twist<S> {
+    sdf: S,
+    rate: f64,
+}
+
+impl<S> Twist<S>
+where
+    S: Sdf,
+{
+    pub fn new(sdf: S, rate: f64) -> Self {
+        Self { sdf, rate }
+    }
+}
+
+impl<S> Sdf for Twist<S>
+where
+    S: Sdf,
+{
+    fn distance(&self, p: &Point) -> f64 {
+        let (s, c) = (self.rate * p[1]).sin_cos();
+        let q = Point::new(c * p[0] - s * p[2], p[1], s * p[0] + c * p[2]);
+        self.sdf.distance(&q)
+    }
+}
+
+#[derive(Clone)]
+pub struct SdfObject<S>
+where
+    S: Sdf,
+{
+    sdf: S,
+    bounds: Aabb,
+    material: Rc<dyn Material>,
+    step_scale: f64,
+}
+
+impl<S> SdfObject<S>
+where
+    S: Sdf,
+{
+    // Marching is confined to bounds, which must enclose the whole surface.
+    pub fn new(sdf: S, bounds: Aabb, material: Rc<dyn Material>) -> Self {
+        Self {
+            sdf,
+            bounds,
+            material,
+            step_scale: 1.0,
+        }
+    }
+    // Shortens every step, for distance functions that overestimate (e.g. twisted ones).
+    pub fn with_step_scale(mut self, step_scale: f64) -> Self {
+        self.step_scale = step_scale;
+        self
+    }
+    fn normal(&self, p: &Point) -> Vec3 {
+        // Tetrahedral central differences need four evaluations instead of six.
+        let k = [
+            Vec3::new(1.0, -1.0, -1.0),
+            Vec3::new(-1.0, -1.0, 1.0),
+            Vec3::new(-1.0, 1.0, -1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+        ];
+        let gradient = k.iter().fold(Vec3::default(), |acc, k| {
+            acc + *k * self.sdf.distance(&(*p + *k * GRADIENT_EPSILON))
+        });
+        gradient.unit()
+    }
+}
+
+impl<S> Hittable for SdfObject<S>
+where
+    S: Sdf,
+{
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let (t0, t1) = match self.bounds.hit_interval(r, t_min, t_max) {
+            Some(interval) => interval,
+            None => return false,
+        };
+        let speed = r.direction.length();
+        // Rays leaving a surface start within the epsilon shell; step out of it first so the
+        // surface they leave is not reported as a hit at the start point.
+        let mut t = t0;
+        let mut start = self.sdf.distance(&r.at(t));
+        let mut steps = 0;
+        while start.abs() < SURFACE_EPSILON {
+            t += SURFACE_EPSILON / speed;
+            steps += 1;
+            if t > t1 || steps == MAX_STEPS {
+                return false;
+            }
+            start = self.sdf.distance(&r.at(t));
+        }
+        // Rays starting inside the surface march towards the boundary from the other side.
+        let side = if start < 0.0 { -1.0 } else { 1.0 };
+        for _ in 0..MAX_STEPS {
+            let d = side * self.sdf.distance(&r.at(t));
+            if d < SURFACE_EPSILON {
+                rec.t = t;
+                rec.point = r.at(t);
+                rec.set_face_normal(r, &self.normal(&rec.point));
+                rec.u = 0.0;
+                rec.v = 0.0;
+                rec.material = self.material.clone();
+                return true;
+            }
+            t += d * self.step_scale / speed;
+            if t > t1 {
+                return false;
+            }
+        }
+        false
+    }
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        *output_box = self.bounds;
+        true
+    }
+}
+
+#[test]
+fn test_sdf_sphere_tracing() {
+    use crate::{material::Lambertian, vec3::Color};
+    let material = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let bounds = Aabb::new(Point::new(-2.0, -2.0, -2.0), Point::new(2.0, 2.0, 2.0));
+    let object = SdfObject::new(
+        RoundedBox::new(Point::default(), Vec3::new(1.0, 1.0, 1.0), 0.2),
+        bounds,
+        material,
+    );
+    let r = Ray {
+        origin: Point::new(0.0, 0.0, -5.0),
+        direction: Vec3::new(0.0, 0.0, 2.0),
+        ..Default::default()
+    };
+    let mut rec = HitRecord::default();
+    assert!(object.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 2.0).abs() < 1e-3);
+    assert!((rec.normal - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-3);
+    assert!(rec.front_face);
+    // From inside, the ray finds the far face.
+    assert!(object.hit(&r, 2.1, f64::INFINITY, &mut rec));
+    assert!((rec.t - 3.0).abs() < 1e-3);
+    assert!(!rec.front_face);
+    // A ray fired from a surface point does not hit that point again: outwards it escapes,
+    // inwards it finds the opposite face.
+    let surface = Point::new(0.0, 0.0, -1.0);
+    let from_surface = |z: f64| Ray {
+        origin: surface,
+        direction: Vec3::new(0.0, 0.0, z),
+        ..Default::default()
+    };
+    assert!(!object.hit(&from_surface(-1.0), 0.0, f64::INFINITY, &mut rec));
+    assert!(object.hit(&from_surface(1.0), 0.0, f64::INFINITY, &mut rec));
+    assert!((rec.t - 2.0).abs() < 1e-3);
+    assert!(!rec.front_face);
+}
@@ -0,0 +1,238 @@
+use crate::vec3::{Color, Vec3};
+use rand::{Rng, RngCore};
+use std::sync::OnceLock;
+
+// Visible range in nanometers.
+pub const LAMBDA_MIN: f64 = 380.0;
+pub const LAMBDA_MAX: f64 = 780.0;
+
+pub fn sample_wavelength(rng: &mut dyn RngCore) -> f64 {
+    rng.gen_range(LAMBDA_MIN..LAMBDA_MAX)
+}
+
+// Uniform wavelength from the `index`-th of `count` equal strata, to reduce color noise per pixel.
+pub fn sample_wavelength_stratified(rng: &mut dyn RngCore, index: usize, count: usize) -> f64 {
+    LAMBDA_MIN + (LAMBDA_MAX - LAMBDA_MIN) * (index as f64 + rng.gen::<f64>()) / count as f64
+}
+
+fn gaussian(x: f64, mu: f64, sigma1: f64, sigma2: f64) -> f64 {
+    let t = (x - mu) / if x < mu { sigma1 } else { sigma2 };
+    (-0.5 * t * t).exp()
+}
+
+// CIE 1931 2-degree color matching functions, using the multi-lobe fit
+// by Wyman, Sloan and Shirley (2013).
+pub fn cie_xyz(lambda: f64) -> Vec3 {
+    Vec3::new(
+        1.056 * gaussian(lambda, 599.8, 37.9, 31.0) + 0.362 * gaussian(lambda, 442.0, 16.0, 26.7)
+            - 0.065 * gaussian(lambda, 501.1, 20.4, 26.2),
+        0.821 * gaussian(lambda, 568.8, 46.9, 40.5) + 0.286 * gaussian(lambda, 530.9, 16.3, 31.1),
+        1.217 * gaussian(lambda, 437.0, 11.8, 36.0) + 0.681 * gaussian(lambda, 459.0, 26.0, 13.8),
+    )
+}
+
+// CIE XYZ to linear sRGB (D65 white point).
+pub fn xyz_to_rgb(xyz: &Vec3) -> Color {
+    Color::new(
+        3.2404542 * xyz[0] - 1.5371385 * xyz[1] - 0.4985314 * xyz[2],
+        -0.9692660 * xyz[0] + 1.8760108 * xyz[1] + 0.0415560 * xyz[2],
+        0.0556434 * xyz[0] - 0.2040259 * xyz[1] + 1.0572252 * xyz[2],
+    )
+}
+
+fn clamped_rgb(lambda: f64) -> Color {
+    let rgb = xyz_to_rgb(&cie_xyz(lambda));
+    Color::new(rgb[0].max(0.0), rgb[1].max(0.0), rgb[2].max(0.0))
+}
+
+// Color weight of a single wavelength sampled uniformly from the visible range.
+// Weights are balanced so that their average over the range is white.
+pub fn wavelength_to_rgb(lambda: f64) -> Color {
+    static AVERAGE: OnceLock<Color> = OnceLock::new();
+    let average = AVERAGE.get_or_init(|| {
+        let steps = (LAMBDA_MAX - LAMBDA_MIN) as usize;
+        let mut sum = Color::default();
+        for i in 0..steps {
+            sum += clamped_rgb(LAMBDA_MIN + i as f64 + 0.5);
+        }
+        sum / steps as f64
+    });
+    let rgb = clamped_rgb(lambda);
+    Color::new(
+        rgb[0] / average[0],
+        rgb[1] / average[1],
+        rgb[2] / average[2],
+    )
+}
+
+// CIE standard illuminant D65 from 380nm to 780nm in 10nm steps, relative to 1.0 at 560nm.
+const D65: [f64; 41] = [
+    0.499755, 0.546482, 0.827549, 0.91486, 0.934318, 0.866823, 1.04865, 1.17008, 1.17812, 1.14861,
+    1.15923, 1.08811, 1.09354, 1.07802, 1.0479, 1.07689, 1.04405, 1.04046, 1.0, 0.963342, 0.95788,
+    0.886856, 0.900062, 0.895991, 0.876987, 0.832886, 0.836992, 0.800268, 0.802146, 0.822778,
+    0.782842, 0.697213, 0.716091, 0.74349, 0.61604, 0.698856, 0.75087, 0.635927, 0.464182,
+    0.668054, 0.633828,
+];
+
+// Basis spectra from Smits, "An RGB to Spectrum Conversion for Reflectances" (1999),
+// sampled at ten wavelengths evenly spaced from 380nm to 720nm.
+const SMITS_WHITE: [f64; 10] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const SMITS_CYAN: [f64; 10] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const SMITS_MAGENTA: [f64; 10] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const SMITS_YELLOW: [f64; 10] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const SMITS_RED: [f64; 10] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const SMITS_GREEN: [f64; 10] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const SMITS_BLUE: [f64; 10] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+fn interpolate(table: &[f64], first: f64, last: f64, lambda: f64) -> f64 {
+    let x = ((lambda - first) / (last - first) * (table.len() - 1) as f64)
+        .clamp(0.0, (table.len() - 1) as f64);
+    let i = (x as usize).min(table.len() - 2);
+    let t = x - i as f64;
+    (1.0 - t) * table[i] + t * table[i + 1]
+}
+
+fn d65(lambda: f64) -> f64 {
+    interpolate(&D65, LAMBDA_MIN, LAMBDA_MAX, lambda)
+}
+
+// Upsamples an RGB reflectance to its spectral value at `lambda`.
+pub fn rgb_to_reflectance(rgb: &Color, lambda: f64) -> f64 {
+    let (r, g, b) = (rgb[0], rgb[1], rgb[2]);
+    let basis = |table: &[f64; 10]| interpolate(table, 380.0, 720.0, lambda);
+    if r <= g && r <= b {
+        r * basis(&SMITS_WHITE)
+            + if g <= b {
+                (g - r) * basis(&SMITS_CYAN) + (b - g) * basis(&SMITS_BLUE)
+            } else {
+                (b - r) * basis(&SMITS_CYAN) + (g - b) * basis(&SMITS_GREEN)
+            }
+    } else if g <= r && g <= b {
+        g * basis(&SMITS_WHITE)
+            + if r <= b {
+                (r - g) * basis(&SMITS_MAGENTA) + (b - r) * basis(&SMITS_BLUE)
+            } else {
+                (b - g) * basis(&SMITS_MAGENTA) + (r - b) * basis(&SMITS_RED)
+            }
+    } else {
+        b * basis(&SMITS_WHITE)
+            + if r <= g {
+                (r - b) * basis(&SMITS_YELLOW) + (g - r) * basis(&SMITS_GREEN)
+            } else {
+                (g - b) * basis(&SMITS_YELLOW) + (r - g) * basis(&SMITS_RED)
+            }
+    }
+}
+
+// Upsamples an RGB emission to its spectral value at `lambda`; white maps to D65.
+pub fn rgb_to_illuminant(rgb: &Color, lambda: f64) -> f64 {
+    rgb_to_reflectance(rgb, lambda) * d65(lambda)
+}
+
+fn integrate<F>(f: F) -> f64
+where
+    F: Fn(f64) -> f64,
+{
+    let steps = (LAMBDA_MAX - LAMBDA_MIN) as usize;
+    (0..steps).map(|i| f(LAMBDA_MIN + i as f64 + 0.5)).sum()
+}
+
+// Luminance of D65, used to normalize spectral radiance so that a white RGB light has Y = 1.
+fn d65_luminance() -> f64 {
+    static LUMINANCE: OnceLock<f64> = OnceLock::new();
+    *LUMINANCE.get_or_init(|| integrate(|lambda| d65(lambda) * cie_xyz(lambda)[1]))
+}
+
+// Contribution to CIE XYZ of a radiance sample at a wavelength picked by `sample_wavelength`.
+pub fn radiance_to_xyz(radiance: f64, lambda: f64) -> Vec3 {
+    radiance * (LAMBDA_MAX - LAMBDA_MIN) / d65_luminance() * cie_xyz(lambda)
+}
+
+// Spectral power distribution of a light source.
+#[derive(Debug, Clone, Copy)]
+pub enum Spd {
+    D65,
+    // Planck's law at `temperature` kelvin, scaled to the luminance of D65.
+    Blackbody { temperature: f64, scale: f64 },
+}
+
+impl Spd {
+    pub fn new_blackbody(temperature: f64) -> Self {
+        let scale =
+            d65_luminance() / integrate(|lambda| planck(lambda, temperature) * cie_xyz(lambda)[1]);
+        Self::Blackbody { temperature, scale }
+    }
+    pub fn value(&self, lambda: f64) -> f64 {
+        match *self {
+            Self::D65 => d65(lambda),
+            Self::Blackbody { temperature, scale } => scale * planck(lambda, temperature),
+        }
+    }
+    pub fn to_rgb(self) -> Color {
+        let xyz = Vec3::new(
+            integrate(|lambda| self.value(lambda) * cie_xyz(lambda)[0]),
+            integrate(|lambda| self.value(lambda) * cie_xyz(lambda)[1]),
+            integrate(|lambda| self.value(lambda) * cie_xyz(lambda)[2]),
+        );
+        xyz_to_rgb(&(xyz / d65_luminance()))
+    }
+}
+
+fn planck(lambda: f64, temperature: f64) -> f64 {
+    const C: f64 = 299792458.0;
+    const H: f64 = 6.62606957e-34;
+    const KB: f64 = 1.3806488e-23;
+    let l = lambda * 1e-9;
+    2.0 * H * C * C / (l.powi(5) * ((H * C / (l * KB * temperature)).exp() - 1.0))
+}
+
+#[test]
+fn test_wavelength_to_rgb_average() {
+    let steps = 4000;
+    let mut sum = Color::default();
+    for i in 0..steps {
+        sum += wavelength_to_rgb(
+            LAMBDA_MIN + (LAMBDA_MAX - LAMBDA_MIN) * (i as f64 + 0.5) / steps as f64,
+        );
+    }
+    let average = sum / steps as f64;
+    for i in 0..3 {
+        assert!((average[i] - 1.0).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn test_white_round_trip() {
+    let white = Color::new(1.0, 1.0, 1.0);
+    let mut xyz = Vec3::default();
+    let steps = 4000;
+    for i in 0..steps {
+        let lambda = LAMBDA_MIN + (LAMBDA_MAX - LAMBDA_MIN) * (i as f64 + 0.5) / steps as f64;
+        xyz += radiance_to_xyz(rgb_to_illuminant(&white, lambda), lambda);
+    }
+    let rgb = xyz_to_rgb(&(xyz / steps as f64));
+    for i in 0..3 {
+        assert!((rgb[i] - 1.0).abs() < 0.05);
+    }
+}
+
+#[test]
+fn test_blackbody_luminance() {
+    let rgb = Spd::new_blackbody(2700.0).to_rgb();
+    assert!(rgb[0] > rgb[1] && rgb[1] > rgb[2]);
+    assert!((rgb.luminance() - 1.0).abs() < 0.05);
+}
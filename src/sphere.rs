@@ -1,8 +1,9 @@
 use crate::{
+    aabb::Aabb,
     hittable::{HitRecord, Hittable},
     material::{Lambertian, Material},
     ray::Ray,
-    vec3::{Color, Point},
+    vec3::{Color, Point, Vec3},
 };
 use std::rc::Rc;
 
@@ -21,6 +22,17 @@ impl Sphere {
             material,
         }
     }
+    pub fn uv(p: &Point) -> (f64, f64) {
+        // p: a given point on the sphere of radius one, centered at the origin.
+        // u: returned value [0,1] of angle around the Y axis from X=-1.
+        // v: returned value [0,1] of angle from Y=-1 to Y=+1.
+        let theta = (-p[1]).acos();
+        let phi = (-p[2]).atan2(p[0]) + std::f64::consts::PI;
+        (
+            phi / (2.0 * std::f64::consts::PI),
+            theta / std::f64::consts::PI,
+        )
+    }
 }
 
 impl Hittable for Sphere {
@@ -49,10 +61,18 @@ impl Hittable for Sphere {
         rec.point = r.at(rec.t);
         let outward_normal = (rec.point - self.center) / self.radius;
         rec.set_face_normal(r, &outward_normal);
+        let (u, v) = Self::uv(&outward_normal);
+        rec.u = u;
+        rec.v = v;
         rec.material = self.material.clone();
 
         true
     }
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        *output_box = Aabb::new(self.center - r, self.center + r);
+        true
+    }
 }
 
 impl Default for Sphere {
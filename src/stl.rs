@@ -0,0 +1,125 @@
+use crate::{
+    mesh::TriangleMesh,
+    vec3::{Point, Vec3},
+};
+use std::{
+    convert::TryFrom,
+    fs::File,
+    io::{self, BufReader, Read},
+    path::Path,
+};
+
+impl TriangleMesh {
+    pub fn load_stl<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::read_stl(&mut BufReader::new(File::open(path)?))
+    }
+    // Reads ASCII or binary STL. Facets do not share vertices; the facet normal is copied to
+    // each corner unless some facet leaves it zero, in which case winding order decides.
+    pub fn read_stl(reader: &mut dyn Read) -> io::Result<Self> {
+        let invalid =
+            |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        // Binary files may also start with "solid", so trust the size check first.
+        let binary_count = if data.len() >= 84 {
+            let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]);
+            usize::try_from(count)
+                .ok()
+                .filter(|&count| 84 + 50 * count == data.len())
+        } else {
+            None
+        };
+        let mut facets: Vec<(Vec3, [Point; 3])> = Vec::new();
+        if let Some(count) = binary_count {
+            let f = |offset: usize| {
+                f64::from(f32::from_le_bytes([
+                    data[offset],
+                    data[offset + 1],
+                    data[offset + 2],
+                    data[offset + 3],
+                ]))
+            };
+            let v = |offset: usize| Vec3::new(f(offset), f(offset + 4), f(offset + 8));
+            for i in 0..count {
+                let base = 84 + 50 * i;
+                facets.push((v(base), [v(base + 12), v(base + 24), v(base + 36)]));
+            }
+        } else {
+            let text = String::from_utf8_lossy(&data);
+            let mut tokens = text.split_whitespace();
+            if tokens.next() != Some("solid") {
+                return Err(invalid("not an STL file"));
+            }
+            let read_vector = |tokens: &mut std::str::SplitWhitespace| -> io::Result<Vec3> {
+                let mut v = Vec3::default();
+                for a in 0..3 {
+                    v[a] = tokens
+                        .next()
+                        .and_then(|token| token.parse().ok())
+                        .ok_or_else(|| invalid("malformed STL number"))?;
+                }
+                Ok(v)
+            };
+            let mut normal = Vec3::default();
+            let mut corners = Vec::new();
+            while let Some(token) = tokens.next() {
+                match token {
+                    "normal" => normal = read_vector(&mut tokens)?,
+                    "vertex" => corners.push(read_vector(&mut tokens)?),
+                    "endfacet" => {
+                        if corners.len() != 3 {
+                            return Err(invalid("STL facet without three vertices"));
+                        }
+                        facets.push((normal, [corners[0], corners[1], corners[2]]));
+                        corners.clear();
+                        normal = Vec3::default();
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let use_normals = facets.iter().all(|(n, _)| !n.is_near_zero());
+        let mut mesh = Self::default();
+        for (normal, corners) in facets.iter() {
+            let start = mesh.positions.len();
+            mesh.positions.extend_from_slice(corners);
+            if use_normals {
+                let normal = normal.unit();
+                mesh.normals.extend_from_slice(&[normal, normal, normal]);
+            }
+            mesh.indices.push([start, start + 1, start + 2]);
+        }
+        Ok(mesh)
+    }
+}
+
+#[test]
+fn test_read_stl() {
+    let ascii = b"solid tri\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 0 0\n\
+vertex 0 1 0\nendloop\nendfacet\nendsolid tri\n";
+    let mesh = TriangleMesh::read_stl(&mut &ascii[..]).unwrap();
+    assert_eq!(mesh.indices, vec![[0, 1, 2]]);
+    assert!((mesh.positions[1] - Point::new(1.0, 0.0, 0.0)).length() < 1e-12);
+    assert!((mesh.normals[0] - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-12);
+
+    let mut binary = b"solid but actually binary".to_vec();
+    binary.resize(80, 0);
+    binary.extend_from_slice(&1u32.to_le_bytes());
+    for x in [
+        0.0f32, 0.0, 0.0, 0.0, 0.0, 0.0, 3.0, 0.0, 0.0, 0.0, 3.0, 0.0,
+    ]
+    .iter()
+    {
+        binary.extend_from_slice(&x.to_le_bytes());
+    }
+    binary.extend_from_slice(&[0, 0]);
+    let mesh = TriangleMesh::read_stl(&mut &binary[..]).unwrap();
+    assert_eq!(mesh.positions.len(), 3);
+    assert!((mesh.positions[2] - Point::new(0.0, 3.0, 0.0)).length() < 1e-12);
+    assert!(mesh.normals.is_empty());
+}
@@ -0,0 +1,486 @@
+use crate::{
+    mesh::TriangleMesh,
+    vec3::{Point, Vec3},
+};
+use std::{collections::HashMap, f64::consts::PI};
+
+// Refinement stops here regardless of what is asked for, as every level multiplies the face
+// count by 4.
+const MAX_LEVELS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubdivisionScheme {
+    // For quad-dominant cages; any polygon is allowed.
+    CatmullClark,
+    // For triangle cages; other polygons are split into fans first.
+    Loop,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Refinement {
+    Levels(usize),
+    // Subdivides until no edge is longer than this.
+    MaxEdgeLength(f64),
+}
+
+// A polygonal control cage. Faces share vertices by index and are wound consistently.
+#[derive(Debug, Clone)]
+pub struct SubdivisionSurface {
+    pub scheme: SubdivisionScheme,
+    pub positions: Vec<Point>,
+    pub faces: Vec<Vec<usize>>,
+    // Creased edges by their end points, with a sharpness that is used up by one each level;
+    // infinity keeps an edge sharp for good. Boundary edges are always sharp.
+    pub creases: Vec<(usize, usize, f64)>,
+}
+
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+fn lerp(t: f64, a: Point, b: Point) -> Point {
+    (1.0 - t) * a + t * b
+}
+
+// Newell's method, so the normal of a non-planar polygon is still sensible. Its length is
+// twice the area.
+fn face_normal(positions: &[Point], face: &[usize]) -> Vec3 {
+    let mut n = Vec3::default();
+    for (i, &a) in face.iter().enumerate() {
+        let b = face[(i + 1) % face.len()];
+        n += positions[a].cross(&positions[b]);
+    }
+    n
+}
+
+struct Level {
+    positions: Vec<Point>,
+    faces: Vec<Vec<usize>>,
+    sharpness: HashMap<(usize, usize), f64>,
+    edge_faces: HashMap<(usize, usize), Vec<usize>>,
+}
+
+impl Level {
+    fn new(
+        positions: Vec<Point>,
+        faces: Vec<Vec<usize>>,
+        sharpness: HashMap<(usize, usize), f64>,
+    ) -> Self {
+        let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (f, face) in faces.iter().enumerate() {
+            for (i, &a) in face.iter().enumerate() {
+                let b = face[(i + 1) % face.len()];
+                edge_faces.entry(edge_key(a, b)).or_default().push(f);
+            }
+        }
+        Self {
+            positions,
+            faces,
+            sharpness,
+            edge_faces,
+        }
+    }
+    fn edge_sharpness(&self, key: &(usize, usize)) -> f64 {
+        if self.edge_faces[key].len() != 2 {
+            f64::INFINITY
+        } else {
+            self.sharpness.get(key).copied().unwrap_or(0.0)
+        }
+    }
+    fn max_edge_length(&self) -> f64 {
+        self.edge_faces
+            .keys()
+            .map(|&(a, b)| (self.positions[a] - self.positions[b]).length())
+            .fold(0.0, f64::max)
+    }
+    // Applies the crease rules to a vertex whose smooth position is `smooth`: along a crease
+    // (two sharp edges) it follows the crease curve, and at a corner it stays put. Corners are
+    // where more than two sharp edges meet, and boundary vertices of a single face.
+    fn vertex_point(&self, v: usize, neighbours: &[usize], smooth: Point) -> Point {
+        let sharp: Vec<(usize, f64)> = neighbours
+            .iter()
+            .map(|&w| (w, self.edge_sharpness(&edge_key(v, w))))
+            .filter(|&(_, s)| s > 0.0)
+            .collect();
+        if sharp.len() < 2 {
+            return smooth;
+        }
+        let p = self.positions[v];
+        let sharp_point = if sharp.len() == 2 && neighbours.len() > 2 {
+            (self.positions[sharp[0].0] + 6.0 * p + self.positions[sharp[1].0]) / 8.0
+        } else {
+            p
+        };
+        let vertex_sharpness = sharp.iter().map(|&(_, s)| s).sum::<f64>() / sharp.len() as f64;
+        lerp(vertex_sharpness.min(1.0), smooth, sharp_point)
+    }
+    fn neighbours(&self) -> Vec<Vec<usize>> {
+        let mut neighbours = vec![Vec::new(); self.positions.len()];
+        for &(a, b) in self.edge_faces.keys() {
+            neighbours[a].push(b);
+            neighbours[b].push(a);
+        }
+        neighbours
+    }
+    // Sharpness of the two halves of each split edge, which end at the new edge point.
+    fn child_sharpness(
+        &self,
+        edge_points: &HashMap<(usize, usize), usize>,
+    ) -> HashMap<(usize, usize), f64> {
+        let mut sharpness = HashMap::new();
+        for (&(a, b), &s) in self.sharpness.iter() {
+            if s > 1.0 && self.edge_faces.contains_key(&(a, b)) {
+                let e = edge_points[&(a, b)];
+                sharpness.insert(edge_key(a, e), s - 1.0);
+                sharpness.insert(edge_key(e, b), s - 1.0);
+            }
+        }
+        sharpness
+    }
+    // Sorted so that the new vertex indices do not depend on hash map order.
+    fn sorted_edges(&self) -> Vec<(usize, usize)> {
+        let mut edges: Vec<(usize, usize)> = self.edge_faces.keys().copied().collect();
+        edges.sort_unstable();
+        edges
+    }
+    fn catmull_clark(&self) -> Self {
+        let n = self.positions.len();
+        let face_points: Vec<Point> = self
+            .faces
+            .iter()
+            .map(|face| {
+                face.iter()
+                    .fold(Point::default(), |sum, &v| sum + self.positions[v])
+                    / face.len() as f64
+            })
+            .collect();
+
+        let mut positions = self.positions.clone();
+        let mut edge_points = HashMap::new();
+        for key in self.sorted_edges() {
+            let (a, b) = key;
+            let mid = 0.5 * (self.positions[a] + self.positions[b]);
+            let faces = &self.edge_faces[&key];
+            let s = self.edge_sharpness(&key);
+            let point = if s >= 1.0 {
+                mid
+            } else {
+                let smooth = 0.5 * (mid + 0.5 * (face_points[faces[0]] + face_points[faces[1]]));
+                lerp(s, smooth, mid)
+            };
+            edge_points.insert(key, positions.len());
+            positions.push(point);
+        }
+        let face_start = positions.len();
+        positions.extend_from_slice(&face_points);
+
+        let mut vertex_faces = vec![Vec::new(); n];
+        for (f, face) in self.faces.iter().enumerate() {
+            for &v in face.iter() {
+                vertex_faces[v].push(f);
+            }
+        }
+        for (v, neighbours) in self.neighbours().iter().enumerate() {
+            if neighbours.is_empty() {
+                continue;
+            }
+            let p = self.positions[v];
+            let valence = neighbours.len() as f64;
+            let q = vertex_faces[v]
+                .iter()
+                .fold(Point::default(), |sum, &f| sum + face_points[f])
+                / vertex_faces[v].len() as f64;
+            let r = neighbours.iter().fold(Point::default(), |sum, &w| {
+                sum + 0.5 * (p + self.positions[w])
+            }) / valence;
+            let smooth = (q + 2.0 * r + (valence - 3.0) * p) / valence;
+            positions[v] = self.vertex_point(v, neighbours, smooth);
+        }
+
+        let mut faces = Vec::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            for (i, &v) in face.iter().enumerate() {
+                let next = face[(i + 1) % face.len()];
+                let previous = face[(i + face.len() - 1) % face.len()];
+                faces.push(vec![
+                    v,
+                    edge_points[&edge_key(v, next)],
+                    face_start + f,
+                    edge_points[&edge_key(previous, v)],
+                ]);
+            }
+        }
+        let sharpness = self.child_sharpness(&edge_points);
+        Self::new(positions, faces, sharpness)
+    }
+    fn loop_subdivision(&self) -> Self {
+        let mut positions = self.positions.clone();
+        let mut edge_points = HashMap::new();
+        for key in self.sorted_edges() {
+            let (a, b) = key;
+            let mid = 0.5 * (self.positions[a] + self.positions[b]);
+            let s = self.edge_sharpness(&key);
+            let point = if s >= 1.0 {
+                mid
+            } else {
+                let opposite = self.edge_faces[&key]
+                    .iter()
+                    .fold(Point::default(), |sum, &f| {
+                        let c = self.faces[f].iter().find(|&&c| c != a && c != b).unwrap();
+                        sum + self.positions[*c]
+                    });
+                lerp(s, 0.75 * mid + 0.125 * opposite, mid)
+            };
+            edge_points.insert(key, positions.len());
+            positions.push(point);
+        }
+        for (v, neighbours) in self.neighbours().iter().enumerate() {
+            if neighbours.is_empty() {
+                continue;
+            }
+            let valence = neighbours.len() as f64;
+            let beta =
+                (5.0 / 8.0 - (3.0 / 8.0 + 0.25 * (2.0 * PI / valence).cos()).powi(2)) / valence;
+            let sum = neighbours
+                .iter()
+                .fold(Point::default(), |sum, &w| sum + self.positions[w]);
+            let smooth = (1.0 - valence * beta) * self.positions[v] + beta * sum;
+            positions[v] = self.vertex_point(v, neighbours, smooth);
+        }
+
+        let mut faces = Vec::new();
+        for face in self.faces.iter() {
+            let (a, b, c) = (face[0], face[1], face[2]);
+            let ab = edge_points[&edge_key(a, b)];
+            let bc = edge_points[&edge_key(b, c)];
+            let ca = edge_points[&edge_key(c, a)];
+            faces.push(vec![a, ab, ca]);
+            faces.push(vec![b, bc, ab]);
+            faces.push(vec![c, ca, bc]);
+            faces.push(vec![ab, bc, ca]);
+        }
+        let sharpness = self.child_sharpness(&edge_points);
+        Self::new(positions, faces, sharpness)
+    }
+    // Triangulates the faces. Normals are averaged around each vertex except across sharp
+    // edges, where the vertex is split so the crease stays visible.
+    fn to_triangle_mesh(&self) -> TriangleMesh {
+        let normals: Vec<Vec3> = self
+            .faces
+            .iter()
+            .map(|face| face_normal(&self.positions, face))
+            .collect();
+        let mut corner_start = Vec::with_capacity(self.faces.len());
+        let mut corners = 0;
+        for face in self.faces.iter() {
+            corner_start.push(corners);
+            corners += face.len();
+        }
+        let corner = |f: usize, v: usize| {
+            corner_start[f] + self.faces[f].iter().position(|&c| c == v).unwrap()
+        };
+
+        // Union-find over face corners that share a smooth edge.
+        let mut parent: Vec<usize> = (0..corners).collect();
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        for key in self.sorted_edges() {
+            let faces = &self.edge_faces[&key];
+            if faces.len() != 2 || self.edge_sharpness(&key) > 0.0 {
+                continue;
+            }
+            for &v in [key.0, key.1].iter() {
+                let i = find(&mut parent, corner(faces[0], v));
+                let j = find(&mut parent, corner(faces[1], v));
+                parent[i] = j;
+            }
+        }
+
+        let mut mesh = TriangleMesh::default();
+        let mut vertex_of_group = HashMap::new();
+        let mut vertices = vec![0; corners];
+        for (f, face) in self.faces.iter().enumerate() {
+            for (k, &v) in face.iter().enumerate() {
+                let group = find(&mut parent, corner_start[f] + k);
+                let index = *vertex_of_group.entry(group).or_insert_with(|| {
+                    mesh.positions.push(self.positions[v]);
+                    mesh.normals.push(Vec3::default());
+                    mesh.positions.len() - 1
+                });
+                mesh.normals[index] += normals[f];
+                vertices[corner_start[f] + k] = index;
+            }
+        }
+        for n in mesh.normals.iter_mut() {
+            if !n.is_near_zero() {
+                *n = n.unit();
+            }
+        }
+        for (f, face) in self.faces.iter().enumerate() {
+            let c = &vertices[corner_start[f]..corner_start[f] + face.len()];
+            if c.len() == 4 {
+                // Split quads along the shorter diagonal.
+                let p = |i: usize| mesh.positions[c[i]];
+                if (p(0) - p(2)).length_squared() <= (p(1) - p(3)).length_squared() {
+                    mesh.indices.push([c[0], c[1], c[2]]);
+                    mesh.indices.push([c[0], c[2], c[3]]);
+                } else {
+                    mesh.indices.push([c[0], c[1], c[3]]);
+                    mesh.indices.push([c[1], c[2], c[3]]);
+                }
+            } else {
+                for i in 1..c.len().saturating_sub(1) {
+                    mesh.indices.push([c[0], c[i], c[i + 1]]);
+                }
+            }
+        }
+        mesh
+    }
+}
+
+impl SubdivisionSurface {
+    pub fn new(scheme: SubdivisionScheme, positions: Vec<Point>, faces: Vec<Vec<usize>>) -> Self {
+        Self {
+            scheme,
+            positions,
+            faces,
+            creases: Vec::new(),
+        }
+    }
+    pub fn from_mesh(scheme: SubdivisionScheme, mesh: &TriangleMesh) -> Self {
+        Self::new(
+            scheme,
+            mesh.positions.clone(),
+            mesh.indices.iter().map(|t| t.to_vec()).collect(),
+        )
+    }
+    pub fn with_crease(mut self, a: usize, b: usize, sharpness: f64) -> Self {
+        self.creases.push((a, b, sharpness));
+        self
+    }
+    pub fn tessellate(&self, refinement: Refinement) -> TriangleMesh {
+        let n = self.positions.len();
+        let mut faces: Vec<Vec<usize>> = self
+            .faces
+            .iter()
+            // Faces that repeat a vertex have no well-defined edges to subdivide.
+            .filter(|face| {
+                face.len() >= 3
+                    && face.iter().all(|&v| v < n)
+                    && face
+                        .iter()
+                        .enumerate()
+                        .all(|(i, v)| !face[i + 1..].contains(v))
+            })
+            .cloned()
+            .collect();
+        if self.scheme == SubdivisionScheme::Loop {
+            faces = faces
+                .iter()
+                .flat_map(|face| {
+                    (1..face.len() - 1).map(move |i| vec![face[0], face[i], face[i + 1]])
+                })
+                .collect();
+        }
+        let sharpness = self
+            .creases
+            .iter()
+            .map(|&(a, b, s)| (edge_key(a, b), s))
+            .collect();
+        let mut level = Level::new(self.positions.clone(), faces, sharpness);
+        let mut count = 0;
+        while match refinement {
+            Refinement::Levels(levels) => count < levels.min(MAX_LEVELS),
+            Refinement::MaxEdgeLength(length) => {
+                count < MAX_LEVELS && level.max_edge_length() > length
+            }
+        } {
+            level = match self.scheme {
+                SubdivisionScheme::CatmullClark => level.catmull_clark(),
+                SubdivisionScheme::Loop => level.loop_subdivision(),
+            };
+            count += 1;
+        }
+        level.to_triangle_mesh()
+    }
+}
+
+#[test]
+fn test_subdivision() {
+    let cube = SubdivisionSurface::new(
+        SubdivisionScheme::CatmullClark,
+        (0..8)
+            .map(|i| {
+                let c = |bit: usize| if i & bit != 0 { 1.0 } else { -1.0 };
+                Point::new(c(1), c(2), c(4))
+            })
+            .collect(),
+        vec![
+            vec![0, 2, 3, 1],
+            vec![4, 5, 7, 6],
+            vec![0, 1, 5, 4],
+            vec![2, 6, 7, 3],
+            vec![0, 4, 6, 2],
+            vec![1, 3, 7, 5],
+        ],
+    );
+    let smooth = cube.tessellate(Refinement::Levels(3));
+    assert_eq!(smooth.indices.len(), 6 * 64 * 2);
+    let mut degenerate = cube.clone();
+    degenerate.faces.push(vec![0, 2, 2, 1]);
+    assert_eq!(
+        degenerate.tessellate(Refinement::Levels(1)).indices.len(),
+        6 * 4 * 2
+    );
+    assert_eq!(smooth.positions.len(), 386);
+    for (p, n) in smooth.positions.iter().zip(smooth.normals.iter()) {
+        assert!(p.length() < 1.5 && p.length() > 0.7);
+        // Outward normals on a convex blob.
+        assert!(p.dot(n) > 0.0);
+    }
+
+    // Creasing every edge keeps the cube, with normals split along the edges.
+    let mut creased = cube.clone();
+    for face in cube.faces.iter() {
+        for i in 0..4 {
+            creased = creased.with_crease(face[i], face[(i + 1) % 4], f64::INFINITY);
+        }
+    }
+    let sharp = creased.tessellate(Refinement::MaxEdgeLength(0.6));
+    assert_eq!(sharp.indices.len(), 6 * 16 * 2);
+    for (p, n) in sharp.positions.iter().zip(sharp.normals.iter()) {
+        let max = p[0].abs().max(p[1].abs()).max(p[2].abs());
+        assert!((max - 1.0).abs() < 1e-12);
+        assert!((n.length() - 1.0).abs() < 1e-12 && n.dot(p) >= 1.0 - 1e-12);
+    }
+
+    // A flat Loop patch stays flat, and keeps the corners that belong to a single face.
+    let patch = SubdivisionSurface::new(
+        SubdivisionScheme::Loop,
+        vec![
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(1.0, 1.0, 0.0),
+        ],
+        // Scanned meshes can carry degenerate triangles; they are dropped.
+        vec![vec![0, 1, 2], vec![2, 1, 3], vec![0, 1, 1]],
+    );
+    let mesh = patch.tessellate(Refinement::Levels(2));
+    assert_eq!(mesh.indices.len(), 2 * 16);
+    // Asking for more levels than the cap gets the cap.
+    let finest = patch.tessellate(Refinement::Levels(usize::MAX));
+    assert_eq!(finest.indices.len(), 2 * 4usize.pow(MAX_LEVELS as u32));
+    assert!(mesh.positions.iter().all(|p| p[2] == 0.0));
+    for corner in [patch.positions[0], patch.positions[3]].iter() {
+        assert!(mesh
+            .positions
+            .iter()
+            .any(|p| (*p - *corner).length() < 1e-12));
+    }
+}
@@ -0,0 +1,238 @@
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    material::{Dielectric, HenyeyGreenstein, Material},
+    ray::Ray,
+    texture::SolidColor,
+    vec3::Color,
+};
+use rand::{Rng, RngCore};
+use std::rc::Rc;
+
+fn average(c: &Color) -> f64 {
+    (c[0] + c[1] + c[2]) / 3.0
+}
+
+fn transmittance(extinction: &Color, distance: f64) -> Color {
+    Color::new(
+        (-extinction[0] * distance).exp(),
+        (-extinction[1] * distance).exp(),
+        (-extinction[2] * distance).exp(),
+    )
+}
+
+// Scales whatever the wrapped material scatters by the throughput weight of the step of the
+// random walk that ended at the hit. The step length is the distance from the ray origin,
+// which is where the walk last turned. With an albedo the step ended in a collision inside
+// the medium, otherwise it reached the boundary.
+#[derive(Clone)]
+struct Weighted {
+    material: Rc<dyn Material>,
+    extinction: Color,
+    albedo: Option<Color>,
+}
+
+impl Weighted {
+    // The one-sample model over the three channels, matching how `Subsurface::hit` samples.
+    fn weight(&self, distance: f64) -> Color {
+        let transmittance = transmittance(&self.extinction, distance);
+        match self.albedo {
+            Some(albedo) => {
+                let density = self.extinction * transmittance;
+                albedo * density / average(&density)
+            }
+            None => transmittance / average(&transmittance),
+        }
+    }
+}
+
+impl Material for Weighted {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+        rng: &mut dyn RngCore,
+    ) -> bool {
+        let scatters = self
+            .material
+            .scatter(r_in, rec, attenuation, scattered, rng);
+        *attenuation = *attenuation * self.weight(rec.t * r_in.direction.length());
+        scatters
+    }
+}
+
+// Translucent object with a dielectric surface and a random walk through its interior.
+// The mean free path is given per color channel, so e.g. red light can travel further in skin.
+// Distances are measured along the rays the materials see, so scale the boundary itself rather
+// than a `Transformed` instance of the whole object.
+#[derive(Clone)]
+pub struct Subsurface<H>
+where
+    H: Hittable,
+{
+    boundary: H,
+    surface: Rc<dyn Material>,
+    // The surface seen from inside, and collisions in the medium.
+    exit: Rc<dyn Material>,
+    medium: Rc<dyn Material>,
+    extinction: Color,
+    albedo: Color,
+}
+
+impl<H> Subsurface<H>
+where
+    H: Hittable,
+{
+    // Panics unless the index of refraction and every mean free path are positive and finite.
+    pub fn new(
+        boundary: H,
+        index_of_refraction: f64,
+        mean_free_path: Color,
+        albedo: Color,
+    ) -> Self {
+        let valid = |x: f64| x > 0.0 && x.is_finite();
+        assert!(
+            valid(index_of_refraction),
+            "subsurface needs a positive, finite index of refraction"
+        );
+        assert!(
+            (0..3).all(|i| valid(mean_free_path[i])),
+            "subsurface needs positive, finite mean free paths"
+        );
+        let surface: Rc<dyn Material> = Rc::new(Dielectric::new(index_of_refraction));
+        let extinction = Color::new(
+            1.0 / mean_free_path[0],
+            1.0 / mean_free_path[1],
+            1.0 / mean_free_path[2],
+        );
+        Self {
+            boundary,
+            exit: Rc::new(Weighted {
+                material: surface.clone(),
+                extinction,
+                albedo: None,
+            }),
+            medium: Self::medium(extinction, albedo, 0.0),
+            surface,
+            extinction,
+            albedo,
+        }
+    }
+    pub fn with_anisotropy(mut self, g: f64) -> Self {
+        self.medium = Self::medium(self.extinction, self.albedo, g.clamp(-0.99, 0.99));
+        self
+    }
+    fn medium(extinction: Color, albedo: Color, g: f64) -> Rc<dyn Material> {
+        let white = Rc::new(SolidColor::new(Color::new(1.0, 1.0, 1.0)));
+        Rc::new(Weighted {
+            material: Rc::new(HenyeyGreenstein::new(white, g)),
+            extinction,
+            albedo: Some(albedo),
+        })
+    }
+}
+
+impl<H> Hittable for Subsurface<H>
+where
+    H: Hittable,
+{
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let mut boundary_rec = HitRecord::default();
+        if !self
+            .boundary
+            .hit(r, t_min, f64::INFINITY, &mut boundary_rec)
+        {
+            return false;
+        }
+
+        if boundary_rec.front_face {
+            if boundary_rec.t > t_max {
+                return false;
+            }
+            *rec = boundary_rec;
+            rec.material = self.surface.clone();
+            return true;
+        }
+
+        // Inside: sample a distance with a uniformly chosen channel's extinction. The materials
+        // weight the step by the one-sample model over all three channels.
+        let mut rng = rand::thread_rng();
+        let ray_length = r.direction.length();
+        let channel = rng.gen_range(0..3);
+        let distance = -(1.0 - rng.gen::<f64>()).ln() / self.extinction[channel];
+        let exit_distance = boundary_rec.t * ray_length;
+
+        if distance < exit_distance {
+            let t = distance / ray_length;
+            if t > t_max {
+                return false;
+            }
+            rec.t = t;
+            rec.point = r.at(t);
+            rec.normal = boundary_rec.normal; // arbitrary
+            rec.front_face = true; // also arbitrary
+            rec.material = self.medium.clone();
+            return true;
+        }
+
+        if boundary_rec.t > t_max {
+            return false;
+        }
+        *rec = boundary_rec;
+        rec.material = self.exit.clone();
+        true
+    }
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        self.boundary.bounding_box(time0, time1, output_box)
+    }
+}
+
+#[test]
+fn test_subsurface_throughput() {
+    use crate::{
+        sphere::Sphere,
+        vec3::{Point, Vec3},
+    };
+
+    // Walks starting at the center of a unit sphere either collide inside, carrying the albedo
+    // of the light that does not get out, or reach the surface with the transmittance.
+    let boundary = Sphere::new(Point::default(), 1.0, Rc::new(Dielectric::new(1.5)));
+    let mean_free_path = Color::new(0.5, 1.0, 2.0);
+    let albedo = Color::new(0.8, 0.6, 0.4);
+    let object = Subsurface::new(boundary, 1.5, mean_free_path, albedo);
+    let mut rng = rand::thread_rng();
+    let n = 40000;
+    let (mut collided, mut escaped) = (Color::default(), Color::default());
+    for _ in 0..n {
+        let r = Ray {
+            direction: Vec3::new(0.0, 0.0, 0.5),
+            ..Ray::default()
+        };
+        let mut rec = HitRecord::default();
+        assert!(object.hit(&r, 0.001, f64::INFINITY, &mut rec));
+        let (mut attenuation, mut scattered) = (Color::default(), Ray::default());
+        rec.material
+            .scatter(&r, &rec, &mut attenuation, &mut scattered, &mut rng);
+        if rec.t < 2.0 - 1e-9 {
+            collided += attenuation;
+        } else {
+            // The dielectric passes everything through, so only the walk weight remains.
+            escaped += attenuation;
+        }
+    }
+    for i in 0..3 {
+        let transmittance = (-1.0 / mean_free_path[i]).exp();
+        assert!((escaped[i] / n as f64 - transmittance).abs() < 0.02);
+        assert!((collided[i] / n as f64 - albedo[i] * (1.0 - transmittance)).abs() < 0.02);
+    }
+
+    for mean_free_path in [Color::new(0.0, 1.0, 1.0), Color::new(1.0, f64::NAN, 1.0)].iter() {
+        let result = std::panic::catch_unwind(|| {
+            let boundary = Sphere::new(Point::default(), 1.0, Rc::new(Dielectric::new(1.5)));
+            Subsurface::new(boundary, 1.5, *mean_free_path, albedo)
+        });
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{
+    hittable::HitRecord,
+    vec3::{Color, Point},
+};
+use std::rc::Rc;
+
+pub trait Texture {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color;
+    // Lookup at a surface hit; textures that need more than (u, v, p) override this.
+    fn value_at(&self, rec: &HitRecord) -> Color {
+        self.value(rec.u, rec.v, &rec.point)
+    }
+}
+
+#[derive(Clone)]
+pub struct SolidColor {
+    color_value: Color,
+}
+
+impl SolidColor {
+    pub fn new(color_value: Color) -> Self {
+        Self { color_value }
+    }
+    pub fn new_scalar(value: f64) -> Self {
+        Self::new(Color::new(value, value, value))
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: &Point) -> Color {
+        self.color_value
+    }
+}
+
+#[derive(Clone)]
+pub struct CheckerTexture {
+    odd: Rc<dyn Texture>,
+    even: Rc<dyn Texture>,
+    scale: f64,
+}
+
+impl CheckerTexture {
+    pub fn new(even: Rc<dyn Texture>, odd: Rc<dyn Texture>, scale: f64) -> Self {
+        Self { odd, even, scale }
+    }
+}
+
+impl CheckerTexture {
+    fn pick(&self, p: &Point) -> &Rc<dyn Texture> {
+        let sines =
+            (self.scale * p[0]).sin() * (self.scale * p[1]).sin() * (self.scale * p[2]).sin();
+        if sines < 0.0 {
+            &self.odd
+        } else {
+            &self.even
+        }
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.pick(p).value(u, v, p)
+    }
+    // Forwards the whole hit so children such as `VertexColor` see it.
+    fn value_at(&self, rec: &HitRecord) -> Color {
+        self.pick(&rec.point).value_at(rec)
+    }
+}
+
+// Uses the color interpolated from mesh vertices, or the fallback where there is none.
+#[derive(Clone)]
+pub struct VertexColor {
+    fallback: Rc<dyn Texture>,
+}
+
+impl VertexColor {
+    pub fn new(fallback: Rc<dyn Texture>) -> Self {
+        Self { fallback }
+    }
+}
+
+impl Texture for VertexColor {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.fallback.value(u, v, p)
+    }
+    fn value_at(&self, rec: &HitRecord) -> Color {
+        rec.vertex_color
+            .unwrap_or_else(|| self.fallback.value_at(rec))
+    }
+}
+
+// Decodes one sRGB-encoded channel in [0, 1] to linear.
+pub fn srgb_to_linear(x: f64) -> f64 {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// Linear RGB pixels in rows from the top, looked up with the nearest pixel and repeated
+// outside [0, 1]. v = 0 is the bottom of the image.
+#[derive(Clone)]
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    pixels: Rc<Vec<Color>>,
+}
+
+impl ImageTexture {
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            width * height,
+            "pixel count must be width * height"
+        );
+        Self {
+            width,
+            height,
+            pixels: Rc::new(pixels),
+        }
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Point) -> Color {
+        if self.pixels.is_empty() {
+            return Color::new(0.0, 1.0, 1.0);
+        }
+        let u = u.rem_euclid(1.0);
+        let v = 1.0 - v.rem_euclid(1.0);
+        let i = ((u * self.width as f64) as usize).min(self.width - 1);
+        let j = ((v * self.height as f64) as usize).min(self.height - 1);
+        self.pixels[j * self.width + i]
+    }
+}
+
+#[test]
+fn test_checker_vertex_color() {
+    let checker = CheckerTexture::new(
+        Rc::new(VertexColor::new(Rc::new(SolidColor::new_scalar(0.0)))),
+        Rc::new(SolidColor::new_scalar(1.0)),
+        1.0,
+    );
+    let rec = HitRecord {
+        point: Point::new(1.0, 1.0, 1.0),
+        vertex_color: Some(Color::new(0.2, 0.4, 0.6)),
+        ..HitRecord::default()
+    };
+    assert!((checker.value_at(&rec) - Color::new(0.2, 0.4, 0.6)).length() < 1e-12);
+    let odd = HitRecord {
+        point: Point::new(-1.0, 1.0, 1.0),
+        ..rec
+    };
+    assert!((checker.value_at(&odd) - Color::new(1.0, 1.0, 1.0)).length() < 1e-12);
+}
@@ -0,0 +1,541 @@
+use crate::{
+    aabb::{surrounding_box, Aabb},
+    hittable::{HitRecord, Hittable},
+    ray::{degrees_to_radians, Ray},
+    vec3::{Direction, Point, Vec3},
+};
+
+// Row-major 4x4 matrix for affine transforms of points, directions and normals.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix4 {
+    m: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn new(m: [[f64; 4]; 4]) -> Self {
+        Self { m }
+    }
+    pub fn identity() -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        Self { m }
+    }
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = self.m[j][i];
+            }
+        }
+        Self { m }
+    }
+    // Gauss-Jordan elimination with partial pivoting; None for singular matrices.
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inv = Self::identity().m;
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().partial_cmp(&a[j][col].abs()).unwrap())
+                .unwrap();
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+            for row in 0..4 {
+                if row != col {
+                    let factor = a[row][col];
+                    for j in 0..4 {
+                        a[row][j] -= factor * a[col][j];
+                        inv[row][j] -= factor * inv[col][j];
+                    }
+                }
+            }
+        }
+        Some(Self { m: inv })
+    }
+    fn is_affine(&self) -> bool {
+        self.m[3] == [0.0, 0.0, 0.0, 1.0]
+    }
+    // Closed-form inverse of an affine matrix: the upper 3x3 block is inverted through its
+    // cofactors and the translation is mapped back through that. Much cheaper than `inverse`,
+    // which matters for transforms rebuilt per ray.
+    fn affine_inverse(&self) -> Option<Self> {
+        let m = &self.m;
+        let cofactor = |i: usize, j: usize| {
+            let (i0, i1) = ((i + 1) % 3, (i + 2) % 3);
+            let (j0, j1) = ((j + 1) % 3, (j + 2) % 3);
+            m[i0][j0] * m[i1][j1] - m[i0][j1] * m[i1][j0]
+        };
+        let det = (0..3).map(|j| m[0][j] * cofactor(0, j)).sum::<f64>();
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let mut inv = Self::identity().m;
+        for (i, row) in inv.iter_mut().take(3).enumerate() {
+            for (j, x) in row.iter_mut().take(3).enumerate() {
+                *x = cofactor(j, i) / det;
+            }
+            row[3] = -(0..3).map(|k| row[k] * m[k][3]).sum::<f64>();
+        }
+        Some(Self { m: inv })
+    }
+    pub fn transform_point(&self, p: &Point) -> Point {
+        let m = &self.m;
+        let x = m[0][0] * p[0] + m[0][1] * p[1] + m[0][2] * p[2] + m[0][3];
+        let y = m[1][0] * p[0] + m[1][1] * p[1] + m[1][2] * p[2] + m[1][3];
+        let z = m[2][0] * p[0] + m[2][1] * p[1] + m[2][2] * p[2] + m[2][3];
+        let w = m[3][0] * p[0] + m[3][1] * p[1] + m[3][2] * p[2] + m[3][3];
+        if w == 1.0 {
+            Point::new(x, y, z)
+        } else {
+            Point::new(x, y, z) / w
+        }
+    }
+    pub fn transform_direction(&self, v: &Direction) -> Direction {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+            m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+            m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+        )
+    }
+}
+
+impl std::ops::Mul for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, rhs: Matrix4) -> Self::Output {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+        Matrix4 { m }
+    }
+}
+
+impl Default for Matrix4 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+// An invertible affine transform together with its inverse.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+impl Transform {
+    pub fn new(matrix: Matrix4) -> Option<Self> {
+        matrix.inverse().map(|inverse| Self { matrix, inverse })
+    }
+    pub fn identity() -> Self {
+        Self::default()
+    }
+    pub fn translate(delta: Vec3) -> Self {
+        let mut m = Matrix4::identity();
+        for a in 0..3 {
+            m.m[a][3] = delta[a];
+        }
+        let mut inv = Matrix4::identity();
+        for a in 0..3 {
+            inv.m[a][3] = -delta[a];
+        }
+        Self {
+            matrix: m,
+            inverse: inv,
+        }
+    }
+    // None when a factor is zero or not finite, as `new` rejects singular matrices.
+    pub fn scale(x: f64, y: f64, z: f64) -> Option<Self> {
+        if [x, y, z].iter().any(|s| s.abs() < 1e-12 || !s.is_finite()) {
+            return None;
+        }
+        Some(Self {
+            matrix: Matrix4::new([
+                [x, 0.0, 0.0, 0.0],
+                [0.0, y, 0.0, 0.0],
+                [0.0, 0.0, z, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ]),
+            inverse: Matrix4::new([
+                [1.0 / x, 0.0, 0.0, 0.0],
+                [0.0, 1.0 / y, 0.0, 0.0],
+                [0.0, 0.0, 1.0 / z, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ]),
+        })
+    }
+    // Rotation by `degrees` around `axis` through the origin.
+    pub fn rotate(degrees: f64, axis: Vec3) -> Self {
+        let a = axis.unit();
+        let (sin, cos) = degrees_to_radians(degrees).sin_cos();
+        let m = Matrix4::new([
+            [
+                a[0] * a[0] + (1.0 - a[0] * a[0]) * cos,
+                a[0] * a[1] * (1.0 - cos) - a[2] * sin,
+                a[0] * a[2] * (1.0 - cos) + a[1] * sin,
+                0.0,
+            ],
+            [
+                a[0] * a[1] * (1.0 - cos) + a[2] * sin,
+                a[1] * a[1] + (1.0 - a[1] * a[1]) * cos,
+                a[1] * a[2] * (1.0 - cos) - a[0] * sin,
+                0.0,
+            ],
+            [
+                a[0] * a[2] * (1.0 - cos) - a[1] * sin,
+                a[1] * a[2] * (1.0 - cos) + a[0] * sin,
+                a[2] * a[2] + (1.0 - a[2] * a[2]) * cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        Self {
+            matrix: m,
+            inverse: m.transpose(),
+        }
+    }
+    // Camera-style transform placing the origin at `lookfrom` looking towards `lookat`.
+    pub fn look_at(lookfrom: Point, lookat: Point, vup: Vec3) -> Option<Self> {
+        let w = (lookat - lookfrom).unit();
+        let u = vup.cross(&w).unit();
+        let v = w.cross(&u);
+        Self::new(Matrix4::new([
+            [u[0], v[0], w[0], lookfrom[0]],
+            [u[1], v[1], w[1], lookfrom[1]],
+            [u[2], v[2], w[2], lookfrom[2]],
+            [0.0, 0.0, 0.0, 1.0],
+        ]))
+    }
+    pub fn matrix(&self) -> Matrix4 {
+        self.matrix
+    }
+    pub fn inverse(&self) -> Self {
+        Self {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+    // The transform that applies `self` first and then `after`.
+    pub fn then(&self, after: &Transform) -> Self {
+        Self {
+            matrix: after.matrix * self.matrix,
+            inverse: self.inverse * after.inverse,
+        }
+    }
+    // True for mirroring transforms, which turn the winding order of triangles around.
+    pub fn swaps_handedness(&self) -> bool {
+        let m = &self.matrix.m;
+        let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+        det < 0.0
+    }
+    pub fn transform_point(&self, p: &Point) -> Point {
+        self.matrix.transform_point(p)
+    }
+    pub fn transform_direction(&self, v: &Direction) -> Direction {
+        self.matrix.transform_direction(v)
+    }
+    // Normals transform with the inverse transpose.
+    pub fn transform_normal(&self, n: &Vec3) -> Vec3 {
+        self.inverse.transpose().transform_direction(n)
+    }
+    pub fn transform_box(&self, b: &Aabb) -> Aabb {
+        let corners: Vec<Point> = b
+            .corners()
+            .iter()
+            .map(|c| self.transform_point(c))
+            .collect();
+        Aabb::from_points(&corners)
+    }
+    // None where the blended matrix is singular, e.g. halfway through a half turn.
+    pub fn interpolate(&self, other: &Transform, t: f64) -> Option<Self> {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = (1.0 - t) * self.matrix.m[i][j] + t * other.matrix.m[i][j];
+            }
+        }
+        let matrix = Matrix4::new(m);
+        let inverse = if matrix.is_affine() {
+            matrix.affine_inverse()
+        } else {
+            matrix.inverse()
+        };
+        inverse.map(|inverse| Self { matrix, inverse })
+    }
+    pub fn transform_ray(&self, r: &Ray) -> Ray {
+        Ray {
+            origin: self.transform_point(&r.origin),
+            direction: self.transform_direction(&r.direction),
+            wavelength: r.wavelength,
+            time: r.time,
+        }
+    }
+}
+
+// An instance of an object placed in the world by `transform`. Wrap the object in an `Rc`
+// to share one copy between many instances.
+#[derive(Clone)]
+pub struct Transformed<H>
+where
+    H: Hittable,
+{
+    object: H,
+    transform: Transform,
+    motion: Option<Motion>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Motion {
+    end: Transform,
+    time0: f64,
+    time1: f64,
+}
+
+impl<H> Transformed<H>
+where
+    H: Hittable,
+{
+    pub fn new(object: H, transform: Transform) -> Self {
+        Self {
+            object,
+            transform,
+            motion: None,
+        }
+    }
+    // An instance moving from `start` at time0 to `end` at time1. The matrices are interpolated
+    // linearly, which is exact for translation and scale and approximate for rotation. Rays miss
+    // the object at times where the blend collapses it.
+    pub fn new_moving(object: H, start: Transform, end: Transform, time0: f64, time1: f64) -> Self {
+        Self {
+            object,
+            transform: start,
+            motion: Some(Motion { end, time0, time1 }),
+        }
+    }
+    fn transform_at(&self, time: f64) -> Option<Transform> {
+        match &self.motion {
+            Some(motion) if motion.time1 > motion.time0 => {
+                let t = ((time - motion.time0) / (motion.time1 - motion.time0)).clamp(0.0, 1.0);
+                self.transform.interpolate(&motion.end, t)
+            }
+            _ => Some(self.transform),
+        }
+    }
+}
+
+impl<H> Hittable for Transformed<H>
+where
+    H: Hittable,
+{
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        // The object space ray keeps the same parameterization, so t needs no conversion.
+        let transform = match self.transform_at(r.time) {
+            Some(transform) => transform,
+            None => return false,
+        };
+        let object_ray = transform.inverse().transform_ray(r);
+        if !self.object.hit(&object_ray, t_min, t_max, rec) {
+            return false;
+        }
+        rec.point = transform.transform_point(&rec.point);
+        let outward_normal = if rec.front_face {
+            rec.normal
+        } else {
+            -rec.normal
+        };
+        let outward_normal = transform.transform_normal(&outward_normal).unit();
+        rec.set_face_normal(r, &outward_normal);
+        rec.tangent = rec
+            .tangent
+            .map(|t| transform.transform_direction(&t).unit());
+        true
+    }
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        let mut object_box = Aabb::default();
+        if !self.object.bounding_box(time0, time1, &mut object_box) {
+            return false;
+        }
+        // Corners move linearly between the end transforms, so both end boxes cover the motion.
+        *output_box = self.transform.transform_box(&object_box);
+        if let Some(motion) = &self.motion {
+            *output_box = surrounding_box(output_box, &motion.end.transform_box(&object_box));
+        }
+        true
+    }
+}
+
+#[test]
+fn test_matrix_inverse() {
+    let t = Transform::translate(Vec3::new(1.0, -2.0, 3.0))
+        .then(&Transform::rotate(30.0, Vec3::new(1.0, 1.0, 0.0)))
+        .then(&Transform::scale(2.0, 0.5, 4.0).unwrap());
+    let inverse = t.matrix().inverse().unwrap();
+    let product = t.matrix() * inverse;
+    let identity = Matrix4::identity();
+    for i in 0..4 {
+        for j in 0..4 {
+            assert!((product.m[i][j] - identity.m[i][j]).abs() < 1e-12);
+        }
+    }
+    let p = Point::new(0.3, 0.7, -1.1);
+    let q = t.inverse().transform_point(&t.transform_point(&p));
+    assert!((p - q).length() < 1e-12);
+    assert!(Matrix4::new([[0.0; 4]; 4]).inverse().is_none());
+}
+
+#[test]
+fn test_moving_transform() {
+    use crate::{material::Lambertian, sphere::Sphere, vec3::Color};
+    use std::rc::Rc;
+
+    let t = Transform::rotate(30.0, Vec3::new(1.0, 1.0, 0.0))
+        .then(&Transform::scale(2.0, 0.5, 4.0).unwrap())
+        .then(&Transform::translate(Vec3::new(1.0, -2.0, 3.0)));
+    let (closed_form, general) = (
+        t.matrix().affine_inverse().unwrap(),
+        t.matrix().inverse().unwrap(),
+    );
+    for i in 0..4 {
+        for j in 0..4 {
+            assert!((closed_form.m[i][j] - general.m[i][j]).abs() < 1e-12);
+        }
+    }
+
+    // A unit sphere growing to radius 2 while moving 4 along x over the shutter interval.
+    let material = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let end = Transform::scale(2.0, 2.0, 2.0)
+        .unwrap()
+        .then(&Transform::translate(Vec3::new(4.0, 0.0, 0.0)));
+    let moving = Transformed::new_moving(
+        Sphere::new(Point::default(), 1.0, material),
+        Transform::identity(),
+        end,
+        0.0,
+        1.0,
+    );
+    let mut bounds = Aabb::default();
+    assert!(moving.bounding_box(0.0, 1.0, &mut bounds));
+    assert!((bounds.min() - Point::new(-1.0, -2.0, -2.0)).length() < 1e-12);
+    assert!((bounds.max() - Point::new(6.0, 2.0, 2.0)).length() < 1e-12);
+
+    // Halfway through, the sphere has radius 1.5 and sits at x = 2.
+    let ray = |x: f64, time: f64| Ray {
+        origin: Point::new(x, 0.0, 5.0),
+        direction: Vec3::new(0.0, 0.0, -1.0),
+        time,
+        ..Ray::default()
+    };
+    let mut rec = HitRecord::default();
+    assert!(!moving.hit(&ray(2.0, 0.0), 0.001, f64::INFINITY, &mut rec));
+    assert!(moving.hit(&ray(2.0, 0.5), 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 3.5).abs() < 1e-9);
+    assert!((rec.normal - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-9);
+    assert!(moving.hit(&ray(4.0, 1.0), 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 3.0).abs() < 1e-9);
+
+    // Blending a half turn about z flattens x and y to nothing at the midpoint, where the
+    // object vanishes instead of snapping back to where it started.
+    let half_turn = Transform::rotate(180.0, Vec3::new(0.0, 0.0, 1.0));
+    assert!(Transform::identity().interpolate(&half_turn, 0.5).is_none());
+    let material = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let spinning = Transformed::new_moving(
+        Sphere::new(Point::default(), 1.0, material),
+        Transform::identity(),
+        half_turn,
+        0.0,
+        1.0,
+    );
+    assert!(spinning.hit(&ray(0.0, 0.0), 0.001, f64::INFINITY, &mut rec));
+    assert!(!spinning.hit(&ray(0.0, 0.5), 0.001, f64::INFINITY, &mut rec));
+    assert!(spinning.hit(&ray(0.0, 1.0), 0.001, f64::INFINITY, &mut rec));
+}
+
+#[test]
+fn test_transformed_normals() {
+    use crate::{material::Lambertian, sphere::Sphere, vec3::Color};
+    use std::rc::Rc;
+
+    assert!(Transform::scale(1.0, 0.0, 1.0).is_none());
+    assert!(Transform::scale(1.0, 1.0, f64::INFINITY).is_none());
+
+    // Stretching a unit sphere to x²/4 + y² + z² = 1 tilts its normals towards y: at
+    // (√2, √2/2, 0) the gradient (x/4, y, z) points along (1, 2, 0).
+    let material = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let ellipsoid = Transformed::new(
+        Sphere::new(Point::default(), 1.0, material),
+        Transform::scale(2.0, 1.0, 1.0).unwrap(),
+    );
+    let point = Point::new(2f64.sqrt(), 0.5f64.sqrt(), 0.0);
+    let normal = Vec3::new(1.0, 2.0, 0.0).unit();
+    let r = Ray {
+        origin: point + 5.0 * normal,
+        direction: -normal,
+        ..Ray::default()
+    };
+    let mut rec = HitRecord::default();
+    assert!(ellipsoid.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 5.0).abs() < 1e-9 && rec.front_face);
+    assert!((rec.point - point).length() < 1e-9);
+    assert!((rec.normal - normal).length() < 1e-9);
+
+    // From inside, the normal is flipped to face the ray.
+    let r = Ray {
+        origin: Point::default(),
+        direction: point,
+        ..Ray::default()
+    };
+    assert!(ellipsoid.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 1.0).abs() < 1e-9 && !rec.front_face);
+    assert!((rec.normal + normal).length() < 1e-9);
+}
+
+#[test]
+fn test_transformed_tangent() {
+    use crate::{
+        curve::{Curve, CurveType},
+        material::Lambertian,
+        vec3::Color,
+    };
+    use std::rc::Rc;
+
+    // A straight fiber along x, turned a quarter round z so it runs along y in the world.
+    let material = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let fiber = Curve::new(
+        [
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(-0.3, 0.0, 0.0),
+            Point::new(0.3, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+        ],
+        0.2,
+        0.2,
+        CurveType::Flat,
+        material,
+    );
+    let rotated = Transformed::new(fiber, Transform::rotate(90.0, Vec3::new(0.0, 0.0, 1.0)));
+    let r = Ray {
+        origin: Point::new(0.0, 0.3, -5.0),
+        direction: Vec3::new(0.0, 0.0, 1.0),
+        ..Ray::default()
+    };
+    let mut rec = HitRecord::default();
+    assert!(rotated.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 5.0).abs() < 1e-6);
+    let tangent = rec.tangent.unwrap();
+    assert!((tangent - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-6);
+}
@@ -75,7 +75,7 @@ impl Vec3 {
         let len = self.length();
         self / len
     }
-    pub fn to_string(&self, samples_per_pixel: f64) -> String {
+    pub fn to_string(self, samples_per_pixel: f64) -> String {
         let scale = 1.0 / samples_per_pixel;
         format!(
             "{} {} {}",
@@ -84,6 +84,9 @@ impl Vec3 {
             (256.0 * (self[2] * scale).sqrt().clamp(0.0, 0.999)) as i64,
         )
     }
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self[0] + 0.7152 * self[1] + 0.0722 * self[2]
+    }
     pub fn is_near_zero(&self) -> bool {
         let s = 1e-8;
         self[0].abs() < s && self[1].abs() < s && self[2].abs() < s